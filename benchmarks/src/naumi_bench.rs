@@ -4,7 +4,7 @@ extern crate test;
 use test::Bencher;
 use lazy_static::lazy_static;
 use naumi::nmacro::NaumiConvert;
-use naumi::types::Convert;
+use naumi::types::{decode_from_slice, Convert};
 
 #[derive(NaumiConvert)]
 struct SomeData {
@@ -63,8 +63,7 @@ fn encode(b: &mut Bencher) {
 #[bench]
 fn decode(b: &mut Bencher) {
     b.iter(|| {
-        let mut data = DATA_ENCODED.clone();
-        let _decoded = Vec::<Vec<SomeData>>::from_bytes(&mut data).unwrap();
+        let _decoded = decode_from_slice::<Vec<Vec<SomeData>>>(&DATA_ENCODED).unwrap();
     });;
 }
 
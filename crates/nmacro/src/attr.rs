@@ -0,0 +1,184 @@
+use syn::punctuated::Punctuated;
+use syn::{Attribute, Error, LitInt, LitStr, Path, Result, Token, WherePredicate};
+
+///
+/// `#[naumi(...)]` on the type.
+///
+#[derive(Default)]
+pub struct TypeAttrs {
+    ///
+    /// `forward`: the type uses the forward layout on its own.
+    ///
+    pub forward: bool,
+
+    ///
+    /// `bound = "..."`: replaces the inferred bounds of the type parameters.
+    ///
+    pub bound: Option<Punctuated<WherePredicate, Token![,]>>,
+
+    ///
+    /// `varint_tag`: enum tags are written as a VarInt instead of a u8, for tags above 255.
+    ///
+    pub varint_tag: bool,
+
+    ///
+    /// `versioned`: the struct is written with its length, so fields marked `since = N` can be appended later.
+    ///
+    pub versioned: bool,
+}
+
+impl TypeAttrs {
+    pub fn parse(attrs: &[Attribute]) -> Result<Self> {
+        let mut res = Self::default();
+        for attr in naumi_attrs(attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("forward") {
+                    if res.forward {
+                        return Err(meta.error("duplicate naumi attribute `forward`"));
+                    }
+                    res.forward = true;
+                } else if meta.path.is_ident("bound") {
+                    if res.bound.is_some() {
+                        return Err(meta.error("duplicate naumi attribute `bound`"));
+                    }
+                    let predicates: LitStr = meta.value()?.parse()?;
+                    res.bound = Some(predicates.parse_with(Punctuated::parse_terminated)?);
+                } else if meta.path.is_ident("varint_tag") {
+                    if res.varint_tag {
+                        return Err(meta.error("duplicate naumi attribute `varint_tag`"));
+                    }
+                    res.varint_tag = true;
+                } else if meta.path.is_ident("versioned") {
+                    if res.versioned {
+                        return Err(meta.error("duplicate naumi attribute `versioned`"));
+                    }
+                    res.versioned = true;
+                } else {
+                    return Err(meta.error("unknown naumi attribute, expected `forward`, `bound = \"...\"`, `varint_tag` or `versioned`"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(res)
+    }
+}
+
+///
+/// `#[naumi(...)]` on an enum variant.
+///
+#[derive(Default)]
+pub struct VariantAttrs {
+    ///
+    /// `tag = N`: tag written for the variant, instead of its discriminant.
+    ///
+    pub tag: Option<u32>,
+
+    ///
+    /// `other`: unknown tags decode into this variant, either a unit variant or one capturing the tag and the payload bytes.
+    ///
+    pub other: bool,
+}
+
+impl VariantAttrs {
+    pub fn parse(attrs: &[Attribute]) -> Result<Self> {
+        let mut res = Self::default();
+        for attr in naumi_attrs(attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("tag") {
+                    if res.tag.is_some() {
+                        return Err(meta.error("duplicate naumi attribute `tag`"));
+                    }
+                    let tag: LitInt = meta.value()?.parse()?;
+                    res.tag = Some(tag.base10_parse()?);
+                } else if meta.path.is_ident("other") {
+                    if res.other {
+                        return Err(meta.error("duplicate naumi attribute `other`"));
+                    }
+                    res.other = true;
+                } else {
+                    return Err(meta.error("unknown naumi attribute, expected `tag = N` or `other`"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(res)
+    }
+}
+
+///
+/// `#[naumi(...)]` on a field of a struct or an enum variant.
+///
+#[derive(Default)]
+pub struct FieldAttrs {
+    ///
+    /// `skip`: the field is not written, decoding fills it with `default` or `Default::default()`.
+    ///
+    pub skip: bool,
+
+    ///
+    /// `default = "path"`: function called to fill a skipped field, or a `since` field missing from older data.
+    ///
+    pub default: Option<Path>,
+
+    ///
+    /// `with = "module"`: the field is converted by `module::encode`, `module::decode` and `module::encoded_len`.
+    ///
+    pub with: Option<Path>,
+
+    ///
+    /// `since = N`: the field was added in version `N` of a `versioned` struct, older data decodes it as its default.
+    ///
+    pub since: Option<LitInt>,
+}
+
+impl FieldAttrs {
+    pub fn parse(attrs: &[Attribute]) -> Result<Self> {
+        let mut res = Self::default();
+        for attr in naumi_attrs(attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    if res.skip {
+                        return Err(meta.error("duplicate naumi attribute `skip`"));
+                    }
+                    res.skip = true;
+                } else if meta.path.is_ident("default") {
+                    if res.default.is_some() {
+                        return Err(meta.error("duplicate naumi attribute `default`"));
+                    }
+                    let path: LitStr = meta.value()?.parse()?;
+                    res.default = Some(path.parse()?);
+                } else if meta.path.is_ident("with") {
+                    if res.with.is_some() {
+                        return Err(meta.error("duplicate naumi attribute `with`"));
+                    }
+                    let path: LitStr = meta.value()?.parse()?;
+                    res.with = Some(path.parse()?);
+                } else if meta.path.is_ident("since") {
+                    if res.since.is_some() {
+                        return Err(meta.error("duplicate naumi attribute `since`"));
+                    }
+                    let version: LitInt = meta.value()?.parse()?;
+                    version.base10_parse::<u32>()?;
+                    res.since = Some(version);
+                } else {
+                    return Err(meta.error("unknown naumi attribute, expected `skip`, `default = \"...\"`, `with = \"...\"` or `since = N`"));
+                }
+                Ok(())
+            })?;
+        }
+        if let (Some(path), false, None) = (&res.default, res.skip, &res.since) {
+            return Err(Error::new_spanned(path, "`default` only applies to fields with `skip` or `since`"));
+        }
+        if let (Some(path), true) = (&res.with, res.skip) {
+            return Err(Error::new_spanned(path, "`with` cannot be combined with `skip`"));
+        }
+        if let (Some(version), true) = (&res.since, res.skip) {
+            return Err(Error::new_spanned(version, "`since` cannot be combined with `skip`"));
+        }
+        Ok(res)
+    }
+}
+
+fn naumi_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|a| a.path().is_ident("naumi"))
+}
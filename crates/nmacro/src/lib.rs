@@ -1,230 +1,587 @@
 extern crate proc_macro;
 
 use proc_macro::TokenStream;
-use cargo_metadata::{MetadataCommand, CargoOpt};
 
-use quote::quote;
+use quote::{format_ident, quote, quote_spanned};
 use syn::*;
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
 
-#[proc_macro_derive(NaumiConvert)]
+use crate::attr::{FieldAttrs, TypeAttrs, VariantAttrs};
+
+mod attr;
+
+#[proc_macro_derive(NaumiConvert, attributes(naumi))]
 pub fn convert(input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
-    let name = &ast.ident;
+    expand(&ast).unwrap_or_else(Error::into_compile_error).into()
+}
 
-    let _metadata = MetadataCommand::new()
-        .manifest_path("./Cargo.toml")
-        .features(CargoOpt::NoDefaultFeatures).exec().unwrap();
-
-    let (mut _net, mut _net_async) = (false, false);
+fn expand(ast: &DeriveInput) -> Result<proc_macro2::TokenStream> {
+    let name = &ast.ident;
+    let type_name = name.unraw().to_string();
 
-    if let Some(deps) = _metadata.root_package() {
-        for d in &deps.dependencies {
-            if d.name == "naumi" {
-                for feature in &d.features {
-                    if feature == &"net".to_string() {
-                        _net = true;
-                    }
-                    if feature == &"net_async".to_string() {
-                        _net_async = true;
-                    }
-                }
-            }
-        }
-    }
-    let net = if _net {
+    let attrs = TypeAttrs::parse(&ast.attrs)?;
+    let layout = if attrs.forward {
         quote! {
-            fn send<T: std::io::Write>(&mut self, tx: &mut T) -> std::io::Result<()> {
-                naumi::types::net::send(self, tx)
-            }
-            fn receive<T: std::io::Read>(rx: &mut T) -> std::io::Result<Self> {
-                naumi::types::net::receive(rx)
-            }
+            const LAYOUT: naumi::types::layout::Layout = naumi::types::layout::Layout::Forward;
         }
     } else {
         quote! {}
     };
 
-    let net_async = if _net_async {
-        quote! {
-            async fn async_send<T: tokio::io::AsyncWriteExt + core::marker::Unpin + tokio::io::AsyncWrite>(&mut self, tx: &mut T) -> std::io::Result<()> {
-                naumi::types::net::async_send(self, tx).await
-            }
-            async fn async_receive<T: tokio::io::AsyncReadExt + core::marker::Unpin + tokio::io::AsyncRead>(rx: &mut T) -> std::io::Result<Self> {
-                naumi::types::net::async_receive(rx).await
-            }
-        }
-    } else {
-        quote! {}
-    };
+    // Lifetime of the buffer being decoded, it has to outlive every lifetime of the type.
+    let de = Lifetime::new("'naumi", proc_macro2::Span::call_site());
+    let mut generics = ast.generics.clone();
+    let bounds = ast.generics.lifetimes().map(|l| l.lifetime.clone()).collect();
+    generics.params.insert(0, GenericParam::Lifetime(LifetimeParam {
+        attrs: vec![],
+        lifetime: de.clone(),
+        colon_token: None,
+        bounds,
+    }));
+    // Every type parameter has to be convertible itself, unless `bound` says otherwise.
+    let predicates = attrs.bound.unwrap_or_else(|| {
+        ast.generics.type_params().map(|t| -> WherePredicate {
+            let ident = &t.ident;
+            parse_quote! { #ident: naumi::types::Convert<#de> }
+        }).collect()
+    });
+    generics.make_where_clause().predicates.extend(predicates);
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = ast.generics.split_for_impl();
 
     let expanded = match &ast.data {
         Data::Struct(data_struct) => {
-            match &data_struct.fields {
-                Fields::Named(fields) => {
-                    let field_to_bytes = fields.named.iter().rev().map(|field| {
-                        let field_name = &field.ident;
-                        quote! {
-                            self.#field_name.to_bytes(tx);
-                        }
-                    });
+            if attrs.varint_tag {
+                return Err(Error::new_spanned(&ast.ident, "`varint_tag` only applies to enums"));
+            }
+
+            let fields = parse_fields(&data_struct.fields)?;
+            let wire = fields.iter().filter(|f| !f.attrs.skip).collect::<Vec<_>>();
 
-                    let field_from_bytes = fields.named.iter().map(|field| {
-                        let field_name = &field.ident;
-                        let field_type = &field.ty;
-                        quote! {
-                            #field_name: <#field_type as naumi::types::Convert>::from_bytes(rx)?,
+            // Fields added later go after the others, oldest version first, so older data simply ends before them.
+            let mut last_since = None;
+            for f in &wire {
+                match (&f.attrs.since, last_since) {
+                    (Some(version), _) if !attrs.versioned => {
+                        return Err(Error::new_spanned(version, "`since` only applies to fields of a `#[naumi(versioned)]` struct"));
+                    },
+                    (Some(version), last) => {
+                        let version = version.base10_parse::<u32>()?;
+                        if let Some(last) = last.filter(|last| version < *last) {
+                            return Err(Error::new_spanned(f.ty, format!("field added in version {version} after a field added in version {last}")));
                         }
-                    });
+                        last_since = Some(version);
+                    },
+                    (None, Some(_)) => {
+                        return Err(Error::new_spanned(f.ty, "fields without `since` go before the fields with `since`"));
+                    },
+                    (None, None) => {},
+                }
+            }
+
+            let field_to_bytes = wire.iter().map(|f| {
+                let member = &f.member;
+                encode_field(f, quote! { (&self.#member) })
+            }).collect::<Vec<_>>();
+            let field_to_bytes_rev = field_to_bytes.iter().rev();
+
+            let field_sizes = wire.iter().map(|f| max_size_field(f, &de));
 
+            let field_len = wire.iter().map(|f| {
+                let member = &f.member;
+                len_field(f, quote! { (&self.#member) })
+            }).collect::<Vec<_>>();
+
+            // Older data ends before the fields added after it.
+            let field_from_bytes = fields.iter().map(|f| {
+                let field_str = &f.name;
+                let value = decode_field(f, &de, quote! { e.field(#field_str) });
+                if f.attrs.since.is_some() {
+                    let default = default_field(f);
+                    quote! { if rx.is_empty() { #default } else { #value } }
+                } else {
+                    value
+                }
+            });
+            let construct = construct(quote! { Self }, &data_struct.fields, field_from_bytes);
+
+            let field_into_bytes = fields.iter().map(|f| {
+                let member = &f.member;
+                let field_str = &f.name;
+                let field_type = f.ty;
+                let into = if f.attrs.skip || f.attrs.with.is_some() {
+                    let value = decode_field(f, &de, quote! { e.field(#field_str) });
+                    quote! { self.#member = #value; }
+                } else {
                     quote! {
-                        impl naumi::types::Convert for #name {
-                            fn to_bytes(&self, tx: &mut Vec<u8>) { #(#field_to_bytes)* }
-                            fn to_bytes_return(&self) -> Vec<u8> {
-                                let mut tx = vec![];
-                                &self.to_bytes(&mut tx);
-                                tx
-                            }
-                            fn from_bytes(rx: &mut Vec<u8>) -> std::io::Result<Self> {
-                                Ok(Self { #(#field_from_bytes)* })
-                            }
-                            #net
-                            #net_async
-                        }
+                        <#field_type as naumi::types::Convert<#de>>::decode_into(&mut self.#member, rx)
+                            .map_err(|e| e.field(#field_str))?;
+                    }
+                };
+                if f.attrs.since.is_some() {
+                    let default = default_field(f);
+                    quote! { if rx.is_empty() { self.#member = #default; } else { #into } }
+                } else {
+                    into
                 }
-            },
-                Fields::Unnamed(_) => {
-                    let field_to_bytes = data_struct.fields.iter().enumerate().rev().map(|(index, _)| {
-                        let index_lit = proc_macro2::Literal::usize_unsuffixed(index);
-                        quote! { self.#index_lit.to_bytes(tx); }
-                    });
-
-                    let field_from_bytes = data_struct.fields.iter().enumerate().map(|(_, field)| {
-                        let field_type = &field.ty;
-                        quote! { <#field_type as naumi::types::Convert>::from_bytes(rx)? }
-                    });
+            });
 
+            // A versioned struct is written with its length, so older readers can skip the fields they do not know.
+            let (encode, len, size) = if attrs.versioned {
+                (
                     quote! {
-                        impl naumi::types::Convert for #name {
-                            fn to_bytes(&self, tx: &mut Vec<u8>) { #(#field_to_bytes)* }
-                            fn to_bytes_return(&self) -> Vec<u8> {
-                                let mut tx = vec![];
-                                &self.to_bytes(&mut tx);
-                                tx
-                            }
-                            fn from_bytes(rx: &mut Vec<u8>) -> std::io::Result<Self> {
-                                Ok(Self( #(#field_from_bytes),* ))
+                        tx.delimited(0 #(+ #field_len)*, |tx| {
+                            match tx.layout() {
+                                naumi::types::layout::Layout::Reverse => { #(#field_to_bytes_rev)* }
+                                naumi::types::layout::Layout::Forward => { #(#field_to_bytes)* }
                             }
-                            #net
-                            #net_async
+                            Ok(())
+                        })
+                    },
+                    quote! {
+                        let len = 0 #(+ #field_len)*;
+                        naumi::types::varint::var_int_len(len as u32) + len
+                    },
+                    quote! { Some(5), },
+                )
+            } else {
+                (
+                    quote! {
+                        match tx.layout() {
+                            naumi::types::layout::Layout::Reverse => { #(#field_to_bytes_rev)* }
+                            naumi::types::layout::Layout::Forward => { #(#field_to_bytes)* }
                         }
-                    }
-                },
+                        Ok(())
+                    },
+                    quote! { 0 #(+ #field_len)* },
+                    quote! {},
+                )
+            };
+            let delimit = |body: proc_macro2::TokenStream| if attrs.versioned {
+                quote! {
+                    let len = rx.read_var_int()? as usize;
+                    rx.delimited(len, |rx| #body)
+                }
+            } else {
+                body
+            };
+            let decode = delimit(quote! { Ok(#construct) });
+            let decode_into = delimit(quote! { { #(#field_into_bytes)* Ok(()) } });
 
-                Fields::Unit => {
-                    panic!("Unit structs are not supported.");
-                },
+            quote! {
+                impl #impl_generics naumi::types::Convert<#de> for #name #ty_generics #where_clause {
+                    #layout
+                    const MAX_SIZE: Option<usize> = naumi::types::size::sum(&[#size #(#field_sizes),*]);
+                    fn encode<__W: naumi::types::encoder::Writer>(&self, tx: &mut naumi::types::encoder::Encoder<__W>) -> naumi::types::error::Result<()> {
+                        #encode
+                    }
+                    fn encoded_len(&self) -> usize {
+                        #len
+                    }
+                    fn decode(rx: &mut naumi::types::decoder::Decoder<#de>) -> naumi::types::error::Result<Self> {
+                        rx.nested(|rx| { #decode })
+                            .map_err(|e| e.within(#type_name))
+                    }
+                    fn decode_into(&mut self, rx: &mut naumi::types::decoder::Decoder<#de>) -> naumi::types::error::Result<()> {
+                        rx.nested(|rx| { #decode_into })
+                            .map_err(|e| e.within(#type_name))
+                    }
+                }
             }
         },
         Data::Enum(data_enum) => {
-            if data_enum.variants.len() > 255 {
-                panic!("Enums with more than 255 variants are not supported due to the limit of u8.");
+            if attrs.versioned {
+                return Err(Error::new_spanned(&ast.ident, "`versioned` only applies to structs"));
             }
 
-            let vars = data_enum.variants.len() as u8;
+            // `#[naumi(other)]` catches unknown tags, so every payload is length-delimited and can be skipped.
+            let mut other: Option<&Variant> = None;
+            // An `other` variant capturing the unknown tag and payload has no tag of its own.
+            let mut capture: Option<&Variant> = None;
 
-            let variants = data_enum.variants.iter().enumerate().map(|(index, v)| {
-                let variant_name = &v.ident;
-                let index = index as u8;
-
-                match &v.fields {
-                    Fields::Unit => quote! {
-                        #name::#variant_name => tx.push(#index),
-                    },
-                    Fields::Unnamed(_) => {
-                        quote! {
-                            #name::#variant_name( field ) => {
-                                field.to_bytes(tx);
-                                tx.push(#index);
-                            }
+            // Tags follow the rules of discriminants: `#[naumi(tag = N)]` or `= N` sets one, the next variants count up from it.
+            let mut tags: Vec<(u32, &Ident)> = Vec::new();
+            let mut next = Some(0u32);
+            for v in &data_enum.variants {
+                let variant_attrs = VariantAttrs::parse(&v.attrs)?;
+                if variant_attrs.other {
+                    if let Some(first) = other {
+                        return Err(Error::new_spanned(&v.ident, format!("duplicate `other` variant, already `{}`", first.ident)));
+                    }
+                    other = Some(v);
+                    if !v.fields.is_empty() {
+                        if v.fields.len() != 2 {
+                            return Err(Error::new_spanned(&v.fields, "an `other` variant is either a unit variant or captures the tag and the payload, e.g. `Unknown(u8, Vec<u8>)`"));
                         }
-                    },
-                    Fields::Named(_) => panic!("Named fields in enum variants are not supported."),
+                        if variant_attrs.tag.is_some() || v.discriminant.is_some() {
+                            return Err(Error::new_spanned(&v.ident, "an `other` variant capturing the tag has no tag of its own"));
+                        }
+                        capture = Some(v);
+                        continue;
+                    }
                 }
-            });
+                let tag = match (variant_attrs.tag, &v.discriminant) {
+                    (Some(tag), _) => tag,
+                    (None, Some((_, expr))) => discriminant(expr)?,
+                    (None, None) => next.ok_or_else(|| Error::new_spanned(&v.ident, "tag does not fit into a u32"))?,
+                };
+                if let Some((_, other)) = tags.iter().find(|(t, _)| *t == tag) {
+                    return Err(Error::new_spanned(&v.ident, format!("duplicate tag {tag}, already used by `{other}`")));
+                }
+                if tag > u8::MAX as u32 && !attrs.varint_tag {
+                    return Err(Error::new_spanned(&v.ident, format!("tag {tag} does not fit into a u8, add `#[naumi(varint_tag)]` to the enum")));
+                }
+                tags.push((tag, &v.ident));
+                next = tag.checked_add(1);
+            }
+
+            let mut variants = Vec::new();
+            let mut variant_lens = Vec::new();
+            let mut variant_sizes = Vec::new();
+            let mut from_variants = Vec::new();
 
-            let from_variants = data_enum.variants.iter().enumerate().map(|(index, v)| {
+            let delimited = other.is_some();
+            let tagged = data_enum.variants.iter().filter(|v| capture.is_none_or(|c| c.ident != v.ident));
+            for (v, (tag, _)) in tagged.zip(&tags) {
                 let variant_name = &v.ident;
-                let index = index as u8;
+                let variant_str = variant_name.unraw().to_string();
+                let (index, write_tag, tag_len) = if attrs.varint_tag {
+                    let index = proc_macro2::Literal::u32_suffixed(*tag);
+                    (index.clone(), quote! { tx.write_var_int(#index) }, var_int_len(*tag))
+                } else {
+                    let index = proc_macro2::Literal::u8_suffixed(*tag as u8);
+                    (index.clone(), quote! { tx.push(#index) }, 1)
+                };
+
+                let fields = parse_fields(&v.fields)?;
+                if let Some(version) = fields.iter().find_map(|f| f.attrs.since.as_ref()) {
+                    return Err(Error::new_spanned(version, "`since` only applies to fields of a `#[naumi(versioned)]` struct"));
+                }
 
+                // Fields are bound to `__field0`, `__field1`, ... so they cannot shadow `tx`, skipped ones are not bound.
+                let bindings = fields.iter().enumerate().map(|(i, f)| {
+                    if f.attrs.skip {
+                        quote! { _ }
+                    } else {
+                        let binding = format_ident!("__field{}", i);
+                        quote! { #binding }
+                    }
+                }).collect::<Vec<_>>();
+                let wire = fields.iter().zip(&bindings).filter(|(f, _)| !f.attrs.skip).collect::<Vec<_>>();
+                let field_to_bytes = wire.iter().map(|(f, binding)| encode_field(f, quote! { #binding })).collect::<Vec<_>>();
+                let field_to_bytes_rev = field_to_bytes.iter().rev();
+                let field_len = wire.iter().map(|(f, binding)| len_field(f, quote! { #binding })).collect::<Vec<_>>();
+                let field_sizes = wire.iter().map(|(f, _)| max_size_field(f, &de));
 
-                if !(index == vars-1 && index != 255){
-                    match &v.fields {
-                        Fields::Unit => quote! {
-                            #index => #name::#variant_name,
-                        },
-                        Fields::Unnamed(fields) => {
-                            let field_type = &fields.unnamed.first().unwrap().ty;
-                            quote! {
-                                #index => {
-                                    let field = <#field_type as naumi::types::Convert>::from_bytes(rx)?;
-                                    #name::#variant_name(field)
-                                }
+                let pattern = match &v.fields {
+                    Fields::Unit => quote! { #name::#variant_name },
+                    Fields::Unnamed(_) => quote! { #name::#variant_name( #(#bindings),* ) },
+                    Fields::Named(_) => {
+                        let field_names = fields.iter().map(|f| &f.member);
+                        quote! { #name::#variant_name { #(#field_names: #bindings),* } }
+                    },
+                };
+
+                // Like the fields of a struct: reversed in `Layout::Reverse`, with the tag written after them.
+                variants.push(if delimited {
+                    quote! {
+                        #pattern => tx.prefixed(|tx| #write_tag, |tx| tx.delimited(0 #(+ #field_len)*, |tx| {
+                            match tx.layout() {
+                                naumi::types::layout::Layout::Reverse => { #(#field_to_bytes_rev)* }
+                                naumi::types::layout::Layout::Forward => { #(#field_to_bytes)* }
                             }
-                        },
-                        Fields::Named(_) => panic!("Named fields in enum variants are not supported."),
+                            Ok(())
+                        })),
+                    }
+                } else if wire.is_empty() {
+                    quote! {
+                        #pattern => #write_tag,
                     }
                 } else {
-                    match &v.fields {
-                        Fields::Unit => quote! {
-                            #index => #name::#variant_name,
-                            _ => return Err(std::io::Error::from(std::io::ErrorKind::InvalidData)),
-                        },
-                        Fields::Unnamed(fields) => {
-                            let field_type = &fields.unnamed.first().unwrap().ty;
-                            quote! {
-                                #index => {
-                                    let field = <#field_type as naumi::types::Convert>::from_bytes(rx)?;
-                                    #name::#variant_name(field)
-                                }
-                                _ => return Err(std::io::Error::from(std::io::ErrorKind::InvalidData)),
-                        }
+                    quote! {
+                        #pattern => tx.prefixed(|tx| #write_tag, |tx| {
+                            match tx.layout() {
+                                naumi::types::layout::Layout::Reverse => { #(#field_to_bytes_rev)* }
+                                naumi::types::layout::Layout::Forward => { #(#field_to_bytes)* }
+                            }
+                            Ok(())
+                        }),
+                    }
+                });
+
+                variant_lens.push(if delimited {
+                    quote! {
+                        #pattern => {
+                            let len = 0 #(+ #field_len)*;
+                            #tag_len + naumi::types::varint::var_int_len(len as u32) + len
                         },
-                        Fields::Named(_) => panic!("Named fields in enum variants are not supported."),
                     }
-                }
-            });
-            quote! {
-                impl naumi::types::Convert for #name {
-                    fn to_bytes(&self, tx: &mut Vec<u8>) {
-                        match self {
-                            #(#variants)*
-                        }
+                } else {
+                    quote! {
+                        #pattern => #tag_len #(+ #field_len)*,
+                    }
+                });
+
+                // The length of a delimited payload takes at most 5 bytes.
+                let len_size = if delimited { quote! { Some(5), } } else { quote! {} };
+                variant_sizes.push(quote! {
+                    naumi::types::size::sum(&[Some(#tag_len), #len_size #(#field_sizes),*])
+                });
+
+                let field_from_bytes = fields.iter().map(|f| {
+                    let field_str = &f.name;
+                    // A single unnamed field is the variant itself, so only the variant goes into the path.
+                    let path = match f.member {
+                        Member::Unnamed(_) if fields.len() == 1 => quote! { e.variant(#variant_str) },
+                        _ => quote! { e.field(#field_str).variant(#variant_str) },
+                    };
+                    decode_field(f, &de, path)
+                });
+                let construct = construct(quote! { #name::#variant_name }, &v.fields, field_from_bytes);
+
+                from_variants.push(if delimited {
+                    quote! {
+                        #index => {
+                            let len = rx.read_var_int()? as usize;
+                            rx.delimited(len, |rx| Ok(#construct))?
+                        },
                     }
-                    fn to_bytes_return(&self) -> Vec<u8> {
-                        let mut tx = vec![];
-                        &self.to_bytes(&mut tx);
-                        tx
+                } else {
+                    quote! {
+                        #index => #construct,
                     }
-                    fn from_bytes(rx: &mut Vec<u8>) -> std::io::Result<Self> {
+                });
+            }
+
+            let read_tag = if attrs.varint_tag {
+                quote! { rx.read_var_int()? }
+            } else {
+                quote! { rx.read_u8()? }
+            };
+
+            // Unknown tags fail, or decode into the `other` variant.
+            let unknown = if let Some(v) = capture {
+                let variant_name = &v.ident;
+                let fields = parse_fields(&v.fields)?;
+                if let Some(f) = fields.iter().find(|f| f.attrs.skip || f.attrs.with.is_some() || f.attrs.since.is_some()) {
+                    return Err(Error::new_spanned(f.ty, "the fields of an `other` variant capturing the tag cannot use naumi attributes"));
+                }
+                let pattern = match &v.fields {
+                    Fields::Named(_) => {
+                        let (tag, payload) = (&fields[0].member, &fields[1].member);
+                        quote! { #name::#variant_name { #tag: __field0, #payload: __field1 } }
+                    },
+                    _ => quote! { #name::#variant_name(__field0, __field1) },
+                };
+                let (write_tag, tag_len) = if attrs.varint_tag {
+                    (quote! { tx.write_var_int(*__field0) }, quote! { naumi::types::varint::var_int_len(*__field0) })
+                } else {
+                    (quote! { tx.push(*__field0) }, quote! { 1 })
+                };
+
+                // The captured tag and payload are written back unchanged.
+                variants.push(quote! {
+                    #pattern => {
+                        let payload: &[u8] = core::convert::AsRef::as_ref(__field1);
+                        tx.prefixed(|tx| #write_tag, |tx| tx.delimited(payload.len(), |tx| tx.write(payload)))
+                    },
+                });
+                variant_lens.push(quote! {
+                    #pattern => {
+                        let len = <_ as core::convert::AsRef<[u8]>>::as_ref(__field1).len();
+                        #tag_len + naumi::types::varint::var_int_len(len as u32) + len
+                    },
+                });
+                variant_sizes.push(quote! { None });
+
+                let construct = construct(quote! { #name::#variant_name }, &v.fields, [
+                    quote! { tag },
+                    quote! { core::convert::From::from(payload) },
+                ].into_iter());
+                quote! {
+                    let len = rx.read_var_int()? as usize;
+                    rx.allocate(len)?;
+                    let payload = rx.read_bytes(len)?;
+                    #construct
+                }
+            } else if let Some(v) = other {
+                let variant_name = &v.ident;
+                quote! {
+                    let len = rx.read_var_int()? as usize;
+                    rx.read_bytes(len)?;
+                    #name::#variant_name
+                }
+            } else {
+                quote! {
+                    return Err(naumi::types::error::Error::new(naumi::types::error::ErrorKind::UnknownTag(u32::from(tag))))
+                }
+            };
+            // Every u8 is taken, so there is no unknown tag left.
+            let unknown_tag = if attrs.varint_tag || tags.len() <= u8::MAX as usize {
+                quote! {
+                    _ => { #unknown }
+                }
+            } else {
+                quote! {}
+            };
+
+            // An enum without variants has no values to encode, and every tag is unknown.
+            let (encode, encoded_len, decode) = if data_enum.variants.is_empty() {
+                (
+                    quote! { match *self {} },
+                    quote! { match *self {} },
+                    quote! { Err(naumi::types::error::Error::new(naumi::types::error::ErrorKind::UnknownTag(u32::from(tag)))) },
+                )
+            } else {
+                (
+                    quote! { match self { #(#variants)* } },
+                    quote! { match self { #(#variant_lens)* } },
+                    quote! {
                         Ok (
-                            if let Some(u) = rx.pop() {
-                                match u as u8 {
-                                    #(#from_variants)*
-                                }
-                            } else {
-                                return Err(std::io::Error::from(std::io::ErrorKind::InvalidData))
+                            match tag {
+                                #(#from_variants)*
+                                #unknown_tag
                             }
                         )
+                    },
+                )
+            };
+
+            quote! {
+                impl #impl_generics naumi::types::Convert<#de> for #name #ty_generics #where_clause {
+                    #layout
+                    const MAX_SIZE: Option<usize> = naumi::types::size::max(&[#(#variant_sizes),*]);
+                    fn encode<__W: naumi::types::encoder::Writer>(&self, tx: &mut naumi::types::encoder::Encoder<__W>) -> naumi::types::error::Result<()> {
+                        #encode
+                    }
+                    fn encoded_len(&self) -> usize {
+                        #encoded_len
+                    }
+                    fn decode(rx: &mut naumi::types::decoder::Decoder<#de>) -> naumi::types::error::Result<Self> {
+                        rx.nested(|rx| {
+                            let tag = #read_tag;
+                            #decode
+                        }).map_err(|e| e.within(#type_name))
                     }
-                    #net
-                    #net_async
                 }
             }
         },
-        Data::Union(_) => {
-            panic!("Union type not supported")
+        Data::Union(data_union) => {
+            return Err(Error::new_spanned(data_union.union_token, "NaumiConvert cannot be derived for unions"));
         },
     };
 
-    TokenStream::from(expanded)
-}
\ No newline at end of file
+    Ok(expanded)
+}
+///
+/// A field of a struct or an enum variant, with its `#[naumi(...)]` attributes.
+///
+struct FieldInfo<'a> {
+    member: Member,
+    // Name used in error paths, the index for unnamed fields.
+    name: String,
+    ty: &'a Type,
+    attrs: FieldAttrs,
+}
+
+fn parse_fields(fields: &Fields) -> Result<Vec<FieldInfo<'_>>> {
+    fields.iter().enumerate().map(|(index, field)| {
+        let (member, name) = match &field.ident {
+            Some(ident) => (Member::Named(ident.clone()), ident.unraw().to_string()),
+            None => (Member::Unnamed(index.into()), index.to_string()),
+        };
+        Ok(FieldInfo { member, name, ty: &field.ty, attrs: FieldAttrs::parse(&field.attrs)? })
+    }).collect()
+}
+
+///
+/// Statement encoding the field, `access` is a reference to its value.
+///
+fn encode_field(f: &FieldInfo, access: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    match &f.attrs.with {
+        Some(module) => quote! { #module::encode(#access, tx)?; },
+        None => quote! { #access.encode(tx)?; },
+    }
+}
+
+///
+/// Encoded length of the field, `access` is a reference to its value.
+///
+fn len_field(f: &FieldInfo, access: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    match &f.attrs.with {
+        Some(module) => quote! { #module::encoded_len(#access) },
+        None => quote! { #access.encoded_len() },
+    }
+}
+
+///
+/// `MAX_SIZE` of the field, unknown for custom codecs.
+///
+fn max_size_field(f: &FieldInfo, de: &Lifetime) -> proc_macro2::TokenStream {
+    let field_type = f.ty;
+    match &f.attrs.with {
+        Some(_) => quote! { None },
+        None => quote! { <#field_type as naumi::types::Convert<#de>>::MAX_SIZE },
+    }
+}
+
+///
+/// Expression decoding the field, `path` adds the field to the error path of `e`.
+///
+fn decode_field(f: &FieldInfo, de: &Lifetime, path: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    if f.attrs.skip {
+        return default_field(f);
+    }
+    let field_type = f.ty;
+    match &f.attrs.with {
+        Some(module) => quote! { #module::decode(rx).map_err(|e| #path)? },
+        None => quote! { <#field_type as naumi::types::Convert<#de>>::decode(rx).map_err(|e| #path)? },
+    }
+}
+
+///
+/// Value of a field that is not on the wire.
+///
+fn default_field(f: &FieldInfo) -> proc_macro2::TokenStream {
+    match &f.attrs.default {
+        Some(path) => quote! { #path() },
+        None => quote_spanned! { f.ty.span()=> core::default::Default::default() },
+    }
+}
+
+///
+/// `path { a: .., b: .. }`, `path(.., ..)` or `path`, depending on the kind of fields.
+///
+fn construct(
+    path: proc_macro2::TokenStream,
+    fields: &Fields,
+    values: impl Iterator<Item = proc_macro2::TokenStream>,
+) -> proc_macro2::TokenStream {
+    match fields {
+        Fields::Named(named) => {
+            let field_names = named.named.iter().map(|field| &field.ident);
+            quote! { #path { #(#field_names: #values),* } }
+        },
+        Fields::Unnamed(_) => quote! { #path( #(#values),* ) },
+        Fields::Unit => path,
+    }
+}
+
+///
+/// Value of an explicit discriminant, used as the tag of the variant.
+///
+fn discriminant(expr: &Expr) -> Result<u32> {
+    match expr {
+        Expr::Lit(ExprLit { lit: Lit::Int(int), .. }) => int.base10_parse(),
+        _ => Err(Error::new_spanned(expr, "expected a non-negative integer literal, or `#[naumi(tag = N)]` on the variant")),
+    }
+}
+
+///
+/// Number of bytes of `tag` written as a VarInt.
+///
+fn var_int_len(tag: u32) -> usize {
+    (38 - (tag | 1).leading_zeros() as usize) / 7
+}
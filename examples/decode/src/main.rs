@@ -1,6 +1,6 @@
 use std::io;
 use naumi::nmacro::NaumiConvert;
-use naumi::types::Convert;
+use naumi::types::{decode_from_slice, Convert};
 use naumi::types::num::varint::VarInt;
 
 #[derive(NaumiConvert, Debug)]
@@ -11,8 +11,18 @@ struct SomeStruct {
 }
 
 fn main() -> io::Result<()> {
-    let mut encoded = [72, 101, 108, 108, 111, 32, 119, 111, 114, 108, 100, 33, 12, 6, 194, 129, 226, 99, 55].to_vec();
+    let encoded = [72, 101, 108, 108, 111, 32, 119, 111, 114, 108, 100, 33, 12, 6, 194, 129, 226, 99, 55];
 
+    // Leaves the buffer untouched.
+    println!("{:?}", decode_from_slice::<SomeStruct>(&encoded)?);
+
+    // Removes the decoded bytes from the vector.
+    let mut encoded = encoded.to_vec();
     println!("{:?}", SomeStruct::from_bytes(&mut encoded)?);
+
+    // Errors point at the value that failed: "SomeStruct.d3: unexpected end of input".
+    if let Err(e) = decode_from_slice::<SomeStruct>(&[72, 101, 108, 108, 111]) {
+        println!("{e}");
+    }
     Ok(())
 }
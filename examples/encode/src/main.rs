@@ -26,4 +26,11 @@ fn main() {
         some_struct.to_bytes(&mut encoded);
         println!("{:?}", encoded)
     }
+
+    {
+        // Any `std::io::Write` works as a sink.
+        let mut encoded = std::io::BufWriter::new(vec![]);
+        some_struct.to_writer(&mut encoded).unwrap();
+        println!("{:?}", encoded.into_inner().unwrap())
+    }
 }
@@ -1,8 +1,6 @@
-#![feature(async_fn_in_trait)]
-
 use std::io;
 use naumi::nmacro::NaumiConvert;
-use naumi::types::Convert;
+use naumi::types::net::AsyncConvert;
 use naumi::types::num::varint::VarInt;
 
 use tokio::net::TcpStream;
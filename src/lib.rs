@@ -1,3 +1,6 @@
-#![feature(async_fn_in_trait)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 pub use nmacro;
 pub mod types;
@@ -0,0 +1,215 @@
+// Every byte coming from the network goes through this cursor, so it must never panic.
+#![deny(
+    clippy::indexing_slicing,
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    clippy::arithmetic_side_effects
+)]
+
+use crate::types::error::{Error, ErrorKind, Result};
+use crate::types::layout::Layout;
+use crate::types::limits::{DecodeLimits, Limit};
+
+///
+/// Cursor over a borrowed buffer.
+///
+/// In `Layout::Reverse` values are read from the end of the buffer (the same way `Convert::from_bytes` does), in `Layout::Forward` from the start.
+///
+/// The input itself is never modified.
+///
+/// Also keeps track of the `DecodeLimits`.
+///
+#[cfg_attr(feature = "clone", derive(Clone))]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct Decoder<'a> {
+    rx: &'a [u8],
+    layout: Layout,
+    limits: DecodeLimits,
+    depth: usize,
+    allocated: usize,
+    lossy_utf8: bool,
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(rx: &'a [u8]) -> Self {
+        Self::with_layout(rx, Layout::Reverse)
+    }
+
+    pub fn with_layout(rx: &'a [u8], layout: Layout) -> Self {
+        Self::with_limits(rx, layout, DecodeLimits::default())
+    }
+
+    pub fn with_limits(rx: &'a [u8], layout: Layout, limits: DecodeLimits) -> Self {
+        Self {
+            rx,
+            layout,
+            limits,
+            depth: 0,
+            allocated: 0,
+            lossy_utf8: false,
+        }
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    pub fn limits(&self) -> &DecodeLimits {
+        &self.limits
+    }
+
+    ///
+    /// Whether owned strings with invalid UTF-8 are accepted, with the bad bytes replaced by U+FFFD.
+    ///
+    pub fn lossy_utf8(&self) -> bool {
+        self.lossy_utf8
+    }
+
+    ///
+    /// Opt into lossy decoding of owned strings, by default invalid UTF-8 is an error.
+    ///
+    pub fn set_lossy_utf8(&mut self, lossy: bool) {
+        self.lossy_utf8 = lossy;
+    }
+
+    ///
+    /// Check the length of a collection against `DecodeLimits::max_len`.
+    ///
+    pub fn limit_len(&mut self, len: usize) -> Result<()> {
+        if len > self.limits.max_len {
+            return Err(Error::new(ErrorKind::LimitExceeded(Limit::Len)));
+        }
+        Ok(())
+    }
+
+    ///
+    /// Check the length of a string against `DecodeLimits::max_str_len`.
+    ///
+    pub fn limit_str_len(&mut self, len: usize) -> Result<()> {
+        if len > self.limits.max_str_len {
+            return Err(Error::new(ErrorKind::LimitExceeded(Limit::StrLen)));
+        }
+        Ok(())
+    }
+
+    ///
+    /// Account `bytes` that are about to be allocated against `DecodeLimits::max_alloc`.
+    ///
+    pub fn allocate(&mut self, bytes: usize) -> Result<()> {
+        self.allocated = self.allocated.saturating_add(bytes);
+        if self.allocated > self.limits.max_alloc {
+            return Err(Error::new(ErrorKind::LimitExceeded(Limit::Alloc)));
+        }
+        Ok(())
+    }
+
+    ///
+    /// Decode one nesting level deeper, checking `DecodeLimits::max_depth`.
+    ///
+    pub fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if self.depth >= self.limits.max_depth {
+            return Err(Error::new(ErrorKind::LimitExceeded(Limit::Depth)));
+        }
+        self.depth = self.depth.saturating_add(1);
+        let result = f(self);
+        self.depth = self.depth.saturating_sub(1);
+        result
+    }
+
+    ///
+    /// Decode from the next `len` bytes only, the bytes `f` does not read are skipped.
+    ///
+    pub fn delimited<T>(
+        &mut self,
+        len: usize,
+        f: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        let payload = self.read_bytes(len)?;
+        let rest = core::mem::replace(&mut self.rx, payload);
+        let result = f(self);
+        self.rx = rest;
+        result
+    }
+
+    ///
+    /// Number of bytes that have not been read yet.
+    ///
+    pub fn remaining(&self) -> usize {
+        self.rx.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rx.is_empty()
+    }
+
+    ///
+    /// The part of the buffer that has not been read yet.
+    ///
+    pub fn rest(&self) -> &'a [u8] {
+        self.rx
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8> {
+        let split = match self.layout {
+            Layout::Reverse => self.rx.split_last(),
+            Layout::Forward => self.rx.split_first(),
+        };
+        match split {
+            Some((u, rest)) => {
+                self.rx = rest;
+                Ok(*u)
+            }
+            None => Err(Error::new(ErrorKind::UnexpectedEnd)),
+        }
+    }
+
+    ///
+    /// Read `len` bytes, keeping their original order.
+    ///
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let split = match self.layout {
+            Layout::Reverse => self
+                .rx
+                .len()
+                .checked_sub(len)
+                .and_then(|at| self.rx.split_at_checked(at))
+                .map(|(rest, bytes)| (bytes, rest)),
+            Layout::Forward => self.rx.split_at_checked(len),
+        };
+        match split {
+            Some((bytes, rest)) => {
+                self.rx = rest;
+                Ok(bytes)
+            }
+            None => Err(Error::new(ErrorKind::UnexpectedEnd)),
+        }
+    }
+
+    pub fn read_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        self.read_bytes(N)?
+            .try_into()
+            .map_err(|_| Error::new(ErrorKind::UnexpectedEnd))
+    }
+
+    ///
+    /// Read a VarInt written by `Encoder::write_var_int`.
+    ///
+    /// VarInts longer than 5 bytes or not fitting into `u32` are rejected.
+    ///
+    pub fn read_var_int(&mut self) -> Result<u32> {
+        let mut value = 0u32;
+        for shift in [0, 7, 14, 21, 28] {
+            let u = self.read_u8()?;
+            if shift == 28 && u > 0x0F {
+                break;
+            }
+            value |= u32::from(u & 0x7F) << shift;
+
+            if (u & 0x80) == 0 {
+                return Ok(value);
+            }
+        }
+        Err(Error::new(ErrorKind::InvalidVarInt))
+    }
+}
@@ -0,0 +1,208 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use crate::types::error::{Error, ErrorKind, Result};
+use crate::types::layout::Layout;
+use crate::types::varint::to_var_int;
+use crate::types::Convert;
+
+///
+/// Destination for encoded bytes.
+///
+/// Implemented for every `std::io::Write` (`Vec<u8>`, `File`, `BufWriter<TcpStream>`, `bytes::BufMut::writer()`, ...).
+///
+/// Without the `std` feature, implemented for `Vec<u8>` and `&mut [u8]`.
+///
+pub trait Writer {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()>;
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write + ?Sized> Writer for W {
+    #[inline]
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        Ok(self.write_all(bytes)?)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl Writer for Vec<u8> {
+    #[inline]
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        self.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+///
+/// Fixed buffer, advanced past the written bytes like `std::io::Write` does.
+///
+/// Fails with `ErrorKind::UnexpectedEnd` when the bytes do not fit, nothing is written then.
+///
+#[cfg(not(feature = "std"))]
+impl Writer for &mut [u8] {
+    #[inline]
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        if bytes.len() > self.len() {
+            return Err(Error::new(ErrorKind::UnexpectedEnd));
+        }
+        let (head, tail) = core::mem::take(self).split_at_mut(bytes.len());
+        head.copy_from_slice(bytes);
+        *self = tail;
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<W: Writer + ?Sized> Writer for &mut W {
+    #[inline]
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        (**self).write_bytes(bytes)
+    }
+}
+
+///
+/// Streams values into a `Writer`.
+///
+#[cfg_attr(feature = "clone", derive(Clone))]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct Encoder<W: Writer> {
+    tx: W,
+    layout: Layout,
+    truncate: bool,
+}
+
+impl<W: Writer> Encoder<W> {
+    pub fn new(tx: W) -> Self {
+        Self::with_layout(tx, Layout::Reverse)
+    }
+
+    pub fn with_layout(tx: W, layout: Layout) -> Self {
+        Self {
+            tx,
+            layout,
+            truncate: false,
+        }
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    pub fn truncates(&self) -> bool {
+        self.truncate
+    }
+
+    ///
+    /// Opt into truncating values that do not fit into their length prefix (`TinyString` longer than 255 bytes, ...).
+    ///
+    /// By default such values fail with `ErrorKind::LengthOverflow`.
+    ///
+    pub fn set_truncate(&mut self, truncate: bool) {
+        self.truncate = truncate;
+    }
+
+    ///
+    /// Number of elements to write out of `len`, when the length prefix holds at most `max`.
+    ///
+    pub fn fit_len(&self, len: usize, max: usize) -> Result<usize> {
+        if len <= max {
+            Ok(len)
+        } else if self.truncate {
+            Ok(max)
+        } else {
+            Err(Error::new(ErrorKind::LengthOverflow))
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.tx
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.tx
+    }
+
+    pub fn into_inner(self) -> W {
+        self.tx
+    }
+
+    #[inline]
+    pub fn push(&mut self, u: u8) -> Result<()> {
+        self.tx.write_bytes(&[u])
+    }
+
+    #[inline]
+    pub fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        self.tx.write_bytes(bytes)
+    }
+
+    ///
+    /// Write a VarInt, reversed in `Layout::Reverse` so it can be read from the end.
+    ///
+    pub fn write_var_int(&mut self, i: u32) -> Result<()> {
+        let (mut t, len) = to_var_int(i);
+        if self.layout == Layout::Reverse {
+            t[..len as usize].reverse();
+        }
+        self.tx.write_bytes(&t[..len as usize])
+    }
+
+    ///
+    /// Write `body` together with its `prefix` (length, tag, ...).
+    ///
+    /// The prefix goes after the body in `Layout::Reverse` and before it in `Layout::Forward`.
+    ///
+    pub fn prefixed(
+        &mut self,
+        prefix: impl FnOnce(&mut Self) -> Result<()>,
+        body: impl FnOnce(&mut Self) -> Result<()>,
+    ) -> Result<()> {
+        match self.layout {
+            Layout::Reverse => {
+                body(self)?;
+                prefix(self)
+            }
+            Layout::Forward => {
+                prefix(self)?;
+                body(self)
+            }
+        }
+    }
+
+    ///
+    /// Write `body` prefixed with its length as a VarInt, `len` has to be its `encoded_len`.
+    ///
+    /// Truncation is turned off inside, so the prefix always matches the bytes written.
+    ///
+    pub fn delimited(
+        &mut self,
+        len: usize,
+        body: impl FnOnce(&mut Self) -> Result<()>,
+    ) -> Result<()> {
+        let len = u32::try_from(len).map_err(|_| Error::new(ErrorKind::LengthOverflow))?;
+        let truncate = core::mem::replace(&mut self.truncate, false);
+        let result = self.prefixed(|tx| tx.write_var_int(len), body);
+        self.truncate = truncate;
+        result
+    }
+
+    ///
+    /// Encode every item, so they are decoded in the same order.
+    ///
+    pub fn encode_all<'a, T: Convert<'a>>(&mut self, items: &[T]) -> Result<()> {
+        match self.layout {
+            Layout::Reverse => {
+                for i in items.iter().rev() {
+                    i.encode(self)?;
+                }
+            }
+            Layout::Forward => {
+                for i in items {
+                    i.encode(self)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
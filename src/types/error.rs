@@ -0,0 +1,236 @@
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+use crate::types::limits::Limit;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+///
+/// What went wrong.
+///
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    ///
+    /// The buffer ended before the value was complete.
+    ///
+    /// Also returned when a fixed `&mut [u8]` is too small for the encoded value, without the `std` feature.
+    ///
+    UnexpectedEnd,
+
+    ///
+    /// A `bool` byte other than 0 or 1.
+    ///
+    InvalidBool(u8),
+
+    ///
+    /// An enum (or `Option`) tag that does not match any variant.
+    ///
+    UnknownTag(u32),
+
+    ///
+    /// A VarInt longer than 5 bytes.
+    ///
+    InvalidVarInt,
+
+    ///
+    /// Bytes that are not valid UTF-8 where a string was expected.
+    ///
+    InvalidUtf8,
+
+    ///
+    /// A length that does not fit into its prefix or into `usize`.
+    ///
+    LengthOverflow,
+
+    ///
+    /// One of the `DecodeLimits` was exceeded.
+    ///
+    LimitExceeded(Limit),
+
+    ///
+    /// A network frame announced more bytes than allowed.
+    ///
+    FrameTooLarge { size: usize, max: usize },
+
+    ///
+    /// The underlying writer or reader failed.
+    ///
+    #[cfg(feature = "std")]
+    Io(io::Error),
+}
+
+///
+/// Part of the path to the value that failed.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    ///
+    /// Named (`.price`) or tuple (`.0`) field.
+    ///
+    Field(&'static str),
+
+    ///
+    /// Element of a collection (`[3]`).
+    ///
+    Index(usize),
+
+    ///
+    /// Enum variant (`::Paid`).
+    ///
+    Variant(&'static str),
+}
+
+///
+/// Encoding or decoding error, with the path to the value that failed (`Order.items[3].price`).
+///
+/// The path is filled in by the `NaumiConvert` derive and the collections while the error travels up.
+///
+#[derive(Debug)]
+pub struct Error {
+    kind: ErrorKind,
+    ty: Option<&'static str>,
+    // Innermost segment first.
+    path: Vec<Segment>,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind) -> Self {
+        Self {
+            kind,
+            ty: None,
+            path: Vec::new(),
+        }
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    ///
+    /// Name of the outermost type the error passed through.
+    ///
+    pub fn type_name(&self) -> Option<&'static str> {
+        self.ty
+    }
+
+    ///
+    /// Path from the outermost type down to the value that failed.
+    ///
+    pub fn path(&self) -> impl Iterator<Item = &Segment> {
+        self.path.iter().rev()
+    }
+
+    pub fn field(mut self, name: &'static str) -> Self {
+        self.path.push(Segment::Field(name));
+        self
+    }
+
+    pub fn index(mut self, index: usize) -> Self {
+        self.path.push(Segment::Index(index));
+        self
+    }
+
+    pub fn variant(mut self, name: &'static str) -> Self {
+        self.path.push(Segment::Variant(name));
+        self
+    }
+
+    ///
+    /// Record the type the error passed through, replacing the previous one.
+    ///
+    pub fn within(mut self, ty: &'static str) -> Self {
+        self.ty = Some(ty);
+        self
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Self::new(kind)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Self::new(ErrorKind::Io(e))
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Error> for io::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error {
+                kind: ErrorKind::Io(inner),
+                ty: None,
+                path,
+            } if path.is_empty() => inner,
+            e => {
+                let kind = match &e.kind {
+                    ErrorKind::UnexpectedEnd => io::ErrorKind::UnexpectedEof,
+                    ErrorKind::Io(inner) => inner.kind(),
+                    _ => io::ErrorKind::InvalidData,
+                };
+                io::Error::new(kind, e)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::UnexpectedEnd => write!(f, "unexpected end of input"),
+            ErrorKind::InvalidBool(u) => write!(f, "invalid bool byte {u}"),
+            ErrorKind::UnknownTag(tag) => write!(f, "unknown tag {tag}"),
+            ErrorKind::InvalidVarInt => write!(f, "invalid VarInt"),
+            ErrorKind::InvalidUtf8 => write!(f, "invalid UTF-8"),
+            ErrorKind::LengthOverflow => write!(f, "length overflow"),
+            ErrorKind::LimitExceeded(limit) => write!(f, "{limit} limit exceeded"),
+            ErrorKind::FrameTooLarge { size, max } => {
+                write!(f, "frame of {size} bytes exceeds the maximum of {max}")
+            }
+            #[cfg(feature = "std")]
+            ErrorKind::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Segment::Field(name) => write!(f, ".{name}"),
+            Segment::Index(index) => write!(f, "[{index}]"),
+            Segment::Variant(name) => write!(f, "::{name}"),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ty.is_none() && self.path.is_empty() {
+            return write!(f, "{}", self.kind);
+        }
+        if let Some(ty) = self.ty {
+            write!(f, "{ty}")?;
+        }
+        for segment in self.path() {
+            write!(f, "{segment}")?;
+        }
+        write!(f, ": {}", self.kind)
+    }
+}
+
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match &self.kind {
+            #[cfg(feature = "std")]
+            ErrorKind::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -1,17 +1,18 @@
-use crate::{impl_net, types};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::hash::Hash;
+use core::mem::size_of;
+#[cfg(feature = "std")]
 use std::collections::{HashMap, HashSet};
-use std::hash::Hash;
-use std::io;
-use std::io::{Error, ErrorKind};
 
-use crate::types::varint::{from_var_int_rev, to_var_int};
-use crate::types::Convert;
-
-#[cfg(feature = "net")]
-use std::io::{Read, Write};
-
-#[cfg(feature = "net_async")]
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use crate::types::decoder::Decoder;
+use crate::types::encoder::{Encoder, Writer};
+use crate::types::error::{Error, ErrorKind, Result};
+#[cfg(feature = "std")]
+use crate::types::layout::Layout;
+use crate::types::limits::{DecodeLimits, Limit};
+use crate::types::varint::var_int_len;
+use crate::types::{size, Convert};
 
 ///
 /// Vector prefixed with u8.
@@ -22,7 +23,7 @@ use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 #[cfg_attr(feature = "debug", derive(Debug))]
 #[cfg_attr(feature = "default_", derive(Default))]
 #[cfg_attr(feature = "partial_eq", derive(PartialEq))]
-pub struct TinyVec<T: Convert>(pub Vec<T>);
+pub struct TinyVec<T>(pub Vec<T>);
 
 ///
 /// Vector prefixed with u16.
@@ -33,7 +34,7 @@ pub struct TinyVec<T: Convert>(pub Vec<T>);
 #[cfg_attr(feature = "debug", derive(Debug))]
 #[cfg_attr(feature = "default_", derive(Default))]
 #[cfg_attr(feature = "partial_eq", derive(PartialEq))]
-pub struct ShortVec<T: Convert>(pub Vec<T>);
+pub struct ShortVec<T>(pub Vec<T>);
 
 ///
 /// Vector prefixed with u32.
@@ -44,7 +45,7 @@ pub struct ShortVec<T: Convert>(pub Vec<T>);
 #[cfg_attr(feature = "debug", derive(Debug))]
 #[cfg_attr(feature = "default_", derive(Default))]
 #[cfg_attr(feature = "partial_eq", derive(PartialEq))]
-pub struct MediumVec<T: Convert>(pub Vec<T>);
+pub struct MediumVec<T>(pub Vec<T>);
 
 ///
 /// Vector prefixed with u64.
@@ -55,291 +56,315 @@ pub struct MediumVec<T: Convert>(pub Vec<T>);
 #[cfg_attr(feature = "debug", derive(Debug))]
 #[cfg_attr(feature = "default_", derive(Default))]
 #[cfg_attr(feature = "partial_eq", derive(PartialEq))]
-pub struct LongVec<T: Convert>(pub Vec<T>);
+pub struct LongVec<T>(pub Vec<T>);
 
-impl<T: Convert> Convert for TinyVec<T> {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        if self.0.len() < 255 {
-            for i in (0..self.0.len()).rev() {
-                self.0[i].to_bytes(tx)
-            }
-            tx.push(self.0.len() as u8);
-        } else {
-            for i in (0..255usize).rev() {
-                self.0[i].to_bytes(tx)
-            }
-            tx.push(255);
-        }
+impl<'a, T: Convert<'a>> Convert<'a> for TinyVec<T> {
+    const MAX_SIZE: Option<usize> =
+        size::sum(&[Some(1), size::repeat(T::MAX_SIZE, u8::MAX as usize)]);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        let len = tx.fit_len(self.0.len(), 255)?;
+        tx.prefixed(|tx| tx.push(len as u8), |tx| tx.encode_all(&self.0[..len]))
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        let mut tx = vec![];
-        &self.to_bytes(&mut tx);
-        tx
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        let size = u8::decode(rx)?;
+        Ok(Self(decode_vec(rx, size as usize)?))
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        if rx.len() < 1 {
-            return Err(Error::new(ErrorKind::InvalidData, ""));
-        }
-
-        let size = u8::from_bytes(rx)?;
-        let mut res = Self(Vec::new());
-        for _ in 0..size {
-            res.0.push(T::from_bytes(rx)?)
-        }
-        Ok(res)
+    fn decode_into(&mut self, rx: &mut Decoder<'a>) -> Result<()> {
+        let size = u8::decode(rx)?;
+        decode_vec_into(rx, &mut self.0, size as usize)
+    }
+    fn encoded_len(&self) -> usize {
+        1 + encoded_len_all(&self.0)
     }
-
-    impl_net!();
 }
 
-impl<T: Convert> Convert for ShortVec<T> {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        if self.0.len() < u16::MAX as usize {
-            for i in (0..self.0.len()).rev() {
-                self.0[i].to_bytes(tx)
-            }
-            tx.extend_from_slice(&self.0.len().to_le_bytes()[0..2]);
-        } else {
-            for i in (0..u16::MAX as usize).rev() {
-                self.0[i].to_bytes(tx)
-            }
-            tx.extend_from_slice(&[255, 255]);
-        }
+impl<'a, T: Convert<'a>> Convert<'a> for ShortVec<T> {
+    const MAX_SIZE: Option<usize> =
+        size::sum(&[Some(2), size::repeat(T::MAX_SIZE, u16::MAX as usize)]);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        let len = tx.fit_len(self.0.len(), u16::MAX as usize)?;
+        tx.prefixed(
+            |tx| tx.write(&(len as u16).to_le_bytes()),
+            |tx| tx.encode_all(&self.0[..len]),
+        )
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        let mut tx = vec![];
-        &self.to_bytes(&mut tx);
-        tx
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        let size = u16::decode(rx)?;
+        Ok(Self(decode_vec(rx, size as usize)?))
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        if rx.len() < 2 {
-            return Err(Error::new(ErrorKind::InvalidData, ""));
-        }
-
-        let size = u16::from_bytes(rx)?;
-        let mut res = Self(Vec::new());
-        for _ in 0..size {
-            res.0.push(T::from_bytes(rx)?)
-        }
-        Ok(res)
+    fn decode_into(&mut self, rx: &mut Decoder<'a>) -> Result<()> {
+        let size = u16::decode(rx)?;
+        decode_vec_into(rx, &mut self.0, size as usize)
+    }
+    fn encoded_len(&self) -> usize {
+        2 + encoded_len_all(&self.0)
     }
-
-    impl_net!();
 }
 
-impl<T: Convert> Convert for MediumVec<T> {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        if self.0.len() < u32::MAX as usize {
-            for i in (0..self.0.len()).rev() {
-                self.0[i].to_bytes(tx)
-            }
-            tx.extend_from_slice(&self.0.len().to_le_bytes()[0..4]);
-        } else {
-            for i in (0..u32::MAX as usize).rev() {
-                self.0[i].to_bytes(tx)
-            }
-            tx.extend_from_slice(&[255, 255, 255, 255]);
-        }
+impl<'a, T: Convert<'a>> Convert<'a> for MediumVec<T> {
+    const MAX_SIZE: Option<usize> =
+        size::sum(&[Some(4), size::repeat(T::MAX_SIZE, u32::MAX as usize)]);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        let len = tx.fit_len(self.0.len(), u32::MAX as usize)?;
+        tx.prefixed(
+            |tx| tx.write(&(len as u32).to_le_bytes()),
+            |tx| tx.encode_all(&self.0[..len]),
+        )
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        let mut tx = vec![];
-        &self.to_bytes(&mut tx);
-        tx
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        let size = u32::decode(rx)?;
+        Ok(Self(decode_vec(rx, size as usize)?))
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        if rx.len() < 4 {
-            return Err(Error::new(ErrorKind::InvalidData, ""));
-        }
-
-        let size = u32::from_bytes(rx)?;
-        let mut res = Self(Vec::new());
-        for _ in 0..size {
-            res.0.push(T::from_bytes(rx)?)
-        }
-        Ok(res)
+    fn decode_into(&mut self, rx: &mut Decoder<'a>) -> Result<()> {
+        let size = u32::decode(rx)?;
+        decode_vec_into(rx, &mut self.0, size as usize)
+    }
+    fn encoded_len(&self) -> usize {
+        4 + encoded_len_all(&self.0)
     }
-
-    impl_net!();
 }
 
-impl<T: Convert> Convert for LongVec<T> {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        if self.0.len() < u64::MAX as usize {
-            for i in (0..self.0.len()).rev() {
-                self.0[i].to_bytes(tx)
-            }
-            tx.extend_from_slice(&self.0.len().to_le_bytes()[0..8]);
-        } else {
-            for i in (0..u64::MAX as usize).rev() {
-                self.0[i].to_bytes(tx)
-            }
-            tx.extend_from_slice(&[255, 255, 255, 255, 255, 255, 255, 255]);
-        }
+impl<'a, T: Convert<'a>> Convert<'a> for LongVec<T> {
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        let len = tx.fit_len(self.0.len(), u64::MAX as usize)?;
+        tx.prefixed(
+            |tx| tx.write(&(len as u64).to_le_bytes()),
+            |tx| tx.encode_all(&self.0[..len]),
+        )
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        let mut tx = vec![];
-        &self.to_bytes(&mut tx);
-        tx
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        let size =
+            usize::try_from(u64::decode(rx)?).map_err(|_| Error::new(ErrorKind::LengthOverflow))?;
+        Ok(Self(decode_vec(rx, size)?))
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        if rx.len() < 8 {
-            return Err(Error::from(ErrorKind::InvalidData));
-        }
-
-        let size = u64::from_bytes(rx)?;
-        let mut res = Self(Vec::new());
-        for _ in 0..size {
-            res.0.push(T::from_bytes(rx)?)
-        }
-        Ok(res)
+    fn decode_into(&mut self, rx: &mut Decoder<'a>) -> Result<()> {
+        let size =
+            usize::try_from(u64::decode(rx)?).map_err(|_| Error::new(ErrorKind::LengthOverflow))?;
+        decode_vec_into(rx, &mut self.0, size)
+    }
+    fn encoded_len(&self) -> usize {
+        8 + encoded_len_all(&self.0)
     }
-
-    impl_net!();
 }
 // Var Int
-impl<T: Convert> Convert for Vec<T> {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        if self.len() < u32::MAX as usize {
-            for i in (0..self.len()).rev() {
-                self[i].to_bytes(tx)
-            }
-            let t = to_var_int(self.len() as u32);
-            let mut t = t.0[..t.1 as usize].to_vec();
-            t.reverse();
-            tx.extend_from_slice(&t);
-        } else {
-            for i in (0..u32::MAX as usize).rev() {
-                self[i].to_bytes(tx)
-            }
-            let t = to_var_int(u32::MAX);
-            let mut t = t.0[..t.1 as usize].to_vec();
-            t.reverse();
-            tx.extend_from_slice(&t);
-        }
+impl<'a, T: Convert<'a>> Convert<'a> for Vec<T> {
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        let len = tx.fit_len(self.len(), u32::MAX as usize)?;
+        tx.prefixed(
+            |tx| tx.write_var_int(len as u32),
+            |tx| tx.encode_all(&self[..len]),
+        )
+    }
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        let size = rx.read_var_int()?;
+        decode_vec(rx, size as usize)
+    }
+    fn decode_into(&mut self, rx: &mut Decoder<'a>) -> Result<()> {
+        let size = rx.read_var_int()?;
+        decode_vec_into(rx, self, size as usize)
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        let mut tx = vec![];
-        &self.to_bytes(&mut tx);
-        tx
+    fn encoded_len(&self) -> usize {
+        var_int_len(self.len() as u32) + encoded_len_all(self)
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        let s = from_var_int_rev(rx)?;
-        rx.truncate(rx.len() - s.1);
+}
 
-        let mut result = Self::new();
-        for _ in 0..s.0 {
-            result.push(T::from_bytes(rx)?);
-        }
+///
+/// Fixed number of elements, written without a length prefix.
+///
+impl<'a, T: Convert<'a>, const N: usize> Convert<'a> for [T; N] {
+    const MAX_SIZE: Option<usize> = size::repeat(T::MAX_SIZE, N);
 
-        Ok(result)
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        tx.encode_all(self)
+    }
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        rx.nested(|rx| {
+            let mut items = Vec::with_capacity(N);
+            for i in 0..N {
+                items.push(T::decode(rx).map_err(|e| e.index(i))?);
+            }
+            // Exactly `N` elements were collected, so the conversion cannot fail.
+            items
+                .try_into()
+                .map_err(|_| Error::new(ErrorKind::UnexpectedEnd))
+        })
+    }
+    fn encoded_len(&self) -> usize {
+        encoded_len_all(self)
     }
+}
 
-    impl_net!();
+///
+/// Borrowed bytes, prefixed with VarInt.
+///
+/// Unlike `Vec<u8>`, the bytes are kept in their original order, so decoding points into the input buffer.
+///
+impl<'a: 'b, 'b> Convert<'a> for &'b [u8] {
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        let len = tx.fit_len(self.len(), u32::MAX as usize)?;
+        tx.prefixed(
+            |tx| tx.write_var_int(len as u32),
+            |tx| tx.write(&self[..len]),
+        )
+    }
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        let size = rx.read_var_int()? as usize;
+        rx.limit_len(size)?;
+        rx.read_bytes(size)
+    }
+    fn encoded_len(&self) -> usize {
+        var_int_len(self.len() as u32) + self.len()
+    }
 }
 
 // Var Int
-impl<T: Convert + Eq + PartialEq + Hash> Convert for HashSet<T> {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        if self.len() < u32::MAX as usize {
-            for i in self {
-                i.to_bytes(tx)
-            }
-            let t = to_var_int(self.len() as u32);
-            let mut t = t.0[..t.1 as usize].to_vec();
-            t.reverse();
-            tx.extend_from_slice(&t);
-        } else {
-            let mut counter = 0u32;
-            for i in self {
-                i.to_bytes(tx);
-                counter += 1;
-                if counter == u32::MAX {
-                    return;
+#[cfg(feature = "std")]
+impl<'a, T: Convert<'a> + Eq + PartialEq + Hash> Convert<'a> for HashSet<T> {
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        let len = tx.fit_len(self.len(), u32::MAX as usize)?;
+        tx.prefixed(
+            |tx| tx.write_var_int(len as u32),
+            |tx| {
+                for i in self.iter().take(len) {
+                    i.encode(tx)?;
                 }
-            }
-            let t = to_var_int(u32::MAX);
-            let mut t = t.0[..t.1 as usize].to_vec();
-            t.reverse();
-            tx.extend_from_slice(&t);
-        }
-    }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        let mut tx = vec![];
-        &self.to_bytes(&mut tx);
-        tx
+                Ok(())
+            },
+        )
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        if rx.len() < 1 {
-            return Err(Error::from(ErrorKind::InvalidData));
-        }
-
-        let size = from_var_int_rev(&rx)?;
-        rx.truncate(rx.len() - size.1);
-
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
         let mut res = Self::new();
-        for _ in 0..size.0 {
-            res.insert(T::from_bytes(rx)?);
-        }
+        res.decode_into(rx)?;
         Ok(res)
     }
-
-    impl_net!();
+    fn decode_into(&mut self, rx: &mut Decoder<'a>) -> Result<()> {
+        let size = rx.read_var_int()? as usize;
+        limit_count::<T>(rx, size)?;
+
+        self.clear();
+        rx.nested(|rx| {
+            for i in 0..size {
+                self.insert(T::decode(rx).map_err(|e| e.index(i))?);
+            }
+            Ok(())
+        })
+    }
+    fn encoded_len(&self) -> usize {
+        var_int_len(self.len() as u32) + self.iter().map(|i| i.encoded_len()).sum::<usize>()
+    }
 }
 
 // Var Int
-impl<T, C> Convert for HashMap<T, C>
+#[cfg(feature = "std")]
+impl<'a, T, C> Convert<'a> for HashMap<T, C>
 where
-    T: Convert + Eq + PartialEq + Hash,
-    C: Convert,
+    T: Convert<'a> + Eq + PartialEq + Hash,
+    C: Convert<'a>,
 {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        if self.len() < u32::MAX as usize {
-            for i in self {
-                i.0.to_bytes(tx);
-                i.1.to_bytes(tx);
-            }
-            let t = to_var_int(self.len() as u32);
-            let mut t = t.0[..t.1 as usize].to_vec();
-            t.reverse();
-            tx.extend_from_slice(&t);
-        } else {
-            let mut counter = 0u32;
-            for i in self {
-                i.0.to_bytes(tx);
-                i.1.to_bytes(tx);
-                counter += 1;
-                if counter == u32::MAX {
-                    return;
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        let len = tx.fit_len(self.len(), u32::MAX as usize)?;
+        tx.prefixed(
+            |tx| tx.write_var_int(len as u32),
+            |tx| {
+                for i in self.iter().take(len) {
+                    i.0.encode(tx)?;
+                    i.1.encode(tx)?;
+                }
+                Ok(())
+            },
+        )
+    }
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        let mut res = Self::new();
+        res.decode_into(rx)?;
+        Ok(res)
+    }
+    fn decode_into(&mut self, rx: &mut Decoder<'a>) -> Result<()> {
+        let size = rx.read_var_int()? as usize;
+        limit_count::<(T, C)>(rx, size)?;
+
+        self.clear();
+        rx.nested(|rx| {
+            for i in 0..size {
+                // Entries are written key first, so in `Layout::Reverse` the value comes out first.
+                match rx.layout() {
+                    Layout::Reverse => {
+                        let j = C::decode(rx).map_err(|e| e.index(i))?;
+                        self.insert(T::decode(rx).map_err(|e| e.index(i))?, j);
+                    }
+                    Layout::Forward => {
+                        let j = T::decode(rx).map_err(|e| e.index(i))?;
+                        self.insert(j, C::decode(rx).map_err(|e| e.index(i))?);
+                    }
                 }
             }
-            let t = to_var_int(u32::MAX);
-            let mut t = t.0[..t.1 as usize].to_vec();
-            t.reverse();
-            tx.extend_from_slice(&t);
-        }
+            Ok(())
+        })
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        let mut tx = vec![];
-        &self.to_bytes(&mut tx);
-        tx
+    fn encoded_len(&self) -> usize {
+        var_int_len(self.len() as u32)
+            + self
+                .iter()
+                .map(|i| i.0.encoded_len() + i.1.encoded_len())
+                .sum::<usize>()
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        if rx.len() < 1 {
-            return Err(Error::from(ErrorKind::InvalidData));
-        }
+}
 
-        let size = from_var_int_rev(&rx)?;
-        rx.truncate(rx.len() - size.1);
+///
+/// Combined length of the encoded elements.
+///
+fn encoded_len_all<'a, T: Convert<'a>>(items: &[T]) -> usize {
+    items.iter().map(|i| i.encoded_len()).sum()
+}
 
-        let mut res = Self::new();
-        for _ in 0..size.0 {
-            let j = C::from_bytes(rx)?;
-            res.insert(T::from_bytes(rx)?, j);
+///
+/// Check a collection of `size` elements before anything is decoded.
+///
+/// Every element takes at least one byte unless `T::MAX_SIZE` is `Some(0)`, so a bigger count cannot be valid.
+///
+fn limit_count<'a, T: Convert<'a>>(rx: &mut Decoder<'a>, size: usize) -> Result<()> {
+    rx.limit_len(size)?;
+    if T::MAX_SIZE == Some(0) {
+        if size > DecodeLimits::MAX_ZERO_SIZED_LEN {
+            return Err(Error::new(ErrorKind::LimitExceeded(Limit::Len)));
         }
-        Ok(res)
+    } else if size > rx.remaining() {
+        return Err(Error::new(ErrorKind::UnexpectedEnd));
     }
-
-    impl_net!();
+    rx.allocate(size.saturating_mul(size_of::<T>()))
 }
 
+///
+/// Decode `size` elements, checking the limits of the decoder first.
+///
+fn decode_vec<'a, T: Convert<'a>>(rx: &mut Decoder<'a>, size: usize) -> Result<Vec<T>> {
+    let mut res = Vec::new();
+    decode_vec_into(rx, &mut res, size)?;
+    Ok(res)
+}
 
+///
+/// Decode `size` elements into `items`, the elements already there are decoded in place.
+///
+fn decode_vec_into<'a, T: Convert<'a>>(
+    rx: &mut Decoder<'a>,
+    items: &mut Vec<T>,
+    size: usize,
+) -> Result<()> {
+    limit_count::<T>(rx, size)?;
+
+    rx.nested(|rx| {
+        items.truncate(size);
+        let reused = items.len();
+        for (i, item) in items.iter_mut().enumerate() {
+            item.decode_into(rx).map_err(|e| e.index(i))?;
+        }
+        for i in reused..size {
+            items.push(T::decode(rx).map_err(|e| e.index(i))?);
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,26 @@
+///
+/// Order in which values are laid out in the encoded bytes.
+///
+/// Decoding always yields fields in declaration order, the layout only decides which end of the buffer they are read from.
+///
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(feature = "default_", derive(Default))]
+pub enum Layout {
+    ///
+    /// Fields are written in reverse, length prefixes and enum tags come after the payload.
+    ///
+    /// The message is decoded from its end, so the whole message has to be available.
+    ///
+    /// Used by default.
+    ///
+    #[cfg_attr(feature = "default_", default)]
+    Reverse,
+
+    ///
+    /// Fields are written in declaration order, length prefixes and enum tags come before the payload.
+    ///
+    /// The message is decoded from its start, so values can be encoded and decoded as a stream.
+    ///
+    Forward,
+}
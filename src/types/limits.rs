@@ -0,0 +1,86 @@
+use core::fmt;
+
+///
+/// Bounds enforced while decoding, so a hostile peer cannot make the decoder allocate or recurse without end.
+///
+/// Every built-in type and the `NaumiConvert` derive check them through `Decoder`.
+///
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(feature = "partial_eq", derive(PartialEq))]
+pub struct DecodeLimits {
+    ///
+    /// Maximum number of bytes the decoded value may allocate in total.
+    ///
+    pub max_alloc: usize,
+
+    ///
+    /// Maximum number of elements in a single collection (`Vec`, `HashMap`, `&[u8]`, ...).
+    ///
+    pub max_len: usize,
+
+    ///
+    /// Maximum length of a single string, in bytes.
+    ///
+    pub max_str_len: usize,
+
+    ///
+    /// Maximum nesting of structs, enums and collections.
+    ///
+    pub max_depth: usize,
+}
+
+impl DecodeLimits {
+    ///
+    /// No limits at all, only use it for trusted input.
+    ///
+    pub const UNLIMITED: Self = Self {
+        max_alloc: usize::MAX,
+        max_len: usize::MAX,
+        max_str_len: usize::MAX,
+        max_depth: usize::MAX,
+    };
+
+    ///
+    /// Maximum number of elements in a collection of types encoded as zero bytes (`()`, `PhantomData`, ...).
+    ///
+    /// Their count cannot be checked against the remaining input, so it is capped whatever the limits are.
+    ///
+    pub const MAX_ZERO_SIZED_LEN: usize = 1 << 16;
+}
+
+///
+/// Sizes are not limited, the nesting is limited to 128 levels.
+///
+/// A collection still cannot announce more elements than there are bytes left, see `MAX_ZERO_SIZED_LEN`.
+///
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 128,
+            ..Self::UNLIMITED
+        }
+    }
+}
+
+///
+/// The limit that was exceeded.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    Alloc,
+    Len,
+    StrLen,
+    Depth,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::Alloc => write!(f, "allocation"),
+            Limit::Len => write!(f, "collection length"),
+            Limit::StrLen => write!(f, "string length"),
+            Limit::Depth => write!(f, "nesting depth"),
+        }
+    }
+}
@@ -1,75 +1,274 @@
-use std::io;
-
+pub mod decoder;
+pub mod encoder;
+pub mod error;
 pub mod iters;
+pub mod layout;
+pub mod limits;
 pub mod num;
 pub mod other;
+///
+/// Helpers to combine `Convert::MAX_SIZE` values in constant expressions.
+///
+/// `None` means the size is not bounded, and it wins over any number.
+///
+pub mod size;
 pub mod string;
 pub mod varint;
 
 #[cfg(feature = "net")]
 use std::io::{Read, Write};
 
-#[cfg(feature = "net_async")]
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use alloc::vec::Vec;
+
+use crate::types::decoder::Decoder;
+use crate::types::encoder::{Encoder, Writer};
+use crate::types::error::Result;
+use crate::types::layout::Layout;
+use crate::types::limits::DecodeLimits;
 
-mod r#macro;
 #[cfg(any(feature = "net", feature = "net_async"))]
 pub mod net;
 
-pub trait Convert {
+///
+/// Convert from a borrowed buffer, leaving it untouched.
+///
+/// The result may borrow from the buffer (e.g. `&str`).
+///
+pub fn decode_from_slice<'a, T: Convert<'a>>(rx: &'a [u8]) -> Result<T> {
+    decode_from_slice_with(rx, T::LAYOUT)
+}
+
+///
+/// Convert from a borrowed buffer encoded with the given layout.
+///
+pub fn decode_from_slice_with<'a, T: Convert<'a>>(rx: &'a [u8], layout: Layout) -> Result<T> {
+    T::decode(&mut Decoder::with_layout(rx, layout))
+}
+
+///
+/// Convert from a borrowed buffer coming from an untrusted source, enforcing `limits`.
+///
+pub fn decode_from_slice_limited<'a, T: Convert<'a>>(
+    rx: &'a [u8],
+    limits: DecodeLimits,
+) -> Result<T> {
+    T::decode(&mut Decoder::with_limits(rx, T::LAYOUT, limits))
+}
+
+///
+/// `'a` is the lifetime of the buffer the value is decoded from, so borrowed types like `&'a str` can point straight into it.
+///
+/// Borrowed bytes are not encoded like their owned counterpart in `Layout::Reverse`:
+/// `Vec<u8>` writes its elements in reverse like any collection, while `&[u8]` keeps them in order so decoding can point into the buffer.
+/// A peer decoding `&[u8]` has to receive `&[u8]`, not `Vec<u8>` (`Layout::Forward` writes both the same way).
+///
+pub trait Convert<'a> {
+    ///
+    /// Layout used when the value is converted on its own (`to_bytes`, `from_bytes`, `send`, ...).
+    ///
+    /// Nested values always follow the layout of the value they are part of.
+    ///
+    const LAYOUT: Layout = Layout::Reverse;
+
+    ///
+    /// Largest number of bytes `encode` can write, `None` if it is not bounded (strings, collections, ...).
+    ///
+    /// Known at compile time, so it can size stack buffers and datagrams:
+    /// `const N: usize = match Point::MAX_SIZE { Some(n) => n, None => panic!() };`
+    ///
+    /// Collections expect one byte per element at least, types that can be encoded as zero bytes have to report `Some(0)`.
+    ///
+    const MAX_SIZE: Option<usize> = None;
+
     ///
     /// Convert to bytes.
     ///
-    fn to_bytes(&self, tx: &mut Vec<u8>);
+    /// Panics if a value does not fit into its length prefix, see `try_to_bytes`.
+    ///
+    fn to_bytes(&self, tx: &mut Vec<u8>) {
+        self.to_bytes_with(tx, Self::LAYOUT)
+    }
+
+    ///
+    /// Convert to bytes with the given layout.
+    ///
+    /// Panics if a value does not fit into its length prefix, see `try_to_bytes_with`.
+    ///
+    fn to_bytes_with(&self, tx: &mut Vec<u8>, layout: Layout) {
+        // Writing to a vector never fails, so only an overflow can get here.
+        if let Err(e) = self.encode(&mut Encoder::with_layout(tx, layout)) {
+            panic!("{e}");
+        }
+    }
 
     ///
     /// Convert to bytes.
     ///
-    fn to_bytes_return(&self) -> Vec<u8>;
+    /// Panics if a value does not fit into its length prefix, see `try_to_bytes`.
+    ///
+    fn to_bytes_return(&self) -> Vec<u8> {
+        let mut tx = Vec::with_capacity(self.encoded_len());
+        self.to_bytes(&mut tx);
+        tx
+    }
+
+    ///
+    /// Convert to bytes, failing with `ErrorKind::LengthOverflow` if a value does not fit into its length prefix.
+    ///
+    /// Use `Encoder::set_truncate` to cut such values instead.
+    ///
+    fn try_to_bytes(&self) -> Result<Vec<u8>> {
+        self.try_to_bytes_with(Self::LAYOUT)
+    }
+
+    ///
+    /// Convert to bytes with the given layout, failing if a value does not fit into its length prefix.
+    ///
+    fn try_to_bytes_with(&self, layout: Layout) -> Result<Vec<u8>> {
+        let mut tx = Vec::with_capacity(self.encoded_len());
+        self.encode(&mut Encoder::with_layout(&mut tx, layout))?;
+        Ok(tx)
+    }
+
+    ///
+    /// Convert directly into any writer (`File`, `BufWriter<TcpStream>`, ...), without an intermediate buffer.
+    ///
+    fn to_writer<W: Writer>(&self, tx: W) -> Result<()> {
+        self.encode(&mut Encoder::with_layout(tx, Self::LAYOUT))
+    }
+
+    ///
+    /// Convert into an encoder.
+    ///
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()>;
+
+    ///
+    /// Number of bytes `encode` writes, computed without encoding.
+    ///
+    /// The same in both layouts. Exact unless a value does not fit into its length prefix.
+    ///
+    fn encoded_len(&self) -> usize;
 
     ///
     /// Convert from bytes.
     ///
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self>
+    /// The decoded bytes are removed from the buffer (from its end in `Layout::Reverse`, from its start in `Layout::Forward`).
+    ///
+    fn from_bytes(rx: &mut Vec<u8>) -> Result<Self>
     where
-        Self: Sized;
+        Self: ConvertOwned,
+    {
+        let layout = <Self as Convert<'a>>::LAYOUT;
+        let mut decoder = Decoder::with_layout(rx, layout);
+        let result = Self::decode_owned(&mut decoder)?;
+        let rest = decoder.remaining();
+        consume(rx, layout, rest);
+        Ok(result)
+    }
 
     ///
-    /// Send the data to the stream and clear the buffer.
+    /// Convert from bytes into an existing value, reusing its allocations (see `decode_into`).
     ///
-    /// Use only this method of sending (Or async variant), because it safely sends data, specifying its length at the beginning, so that nothing is lost or stuck together.
+    /// The decoded bytes are removed from the buffer, like with `from_bytes`.
     ///
-    #[cfg(feature = "net")]
-    fn send<T: Write>(&mut self, tx: &mut T) -> io::Result<()>;
+    fn update_from_bytes(&mut self, rx: &mut Vec<u8>) -> Result<()>
+    where
+        Self: ConvertOwned,
+    {
+        let layout = <Self as Convert<'a>>::LAYOUT;
+        let mut decoder = Decoder::with_layout(rx, layout);
+        self.decode_owned_into(&mut decoder)?;
+        let rest = decoder.remaining();
+        consume(rx, layout, rest);
+        Ok(())
+    }
 
     ///
-    /// Send the data to the stream and clear the buffer.
+    /// Convert from a decoder without modifying the underlying buffer.
     ///
-    /// Use only this method of sending (Or syn variant), because it safely sends data, specifying its length at the beginning, so that nothing is lost or stuck together.
+    /// Never panics on malformed input, an error is returned instead.
     ///
-    #[cfg(feature = "net_async")]
-    async fn async_send<T: AsyncWriteExt + Unpin + AsyncWrite>(
-        &mut self,
-        tx: &mut T,
-    ) -> io::Result<()>;
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self>
+    where
+        Self: Sized;
 
     ///
-    /// Get data from stream.
+    /// Convert from a decoder into an existing value, reusing its allocations.
+    ///
+    /// `Vec`, `String`, `HashMap`, `HashSet`, their prefixed variants and derived structs refill their storage in place,
+    /// other types are replaced. On error the value is left in a valid but unspecified state.
+    ///
+    fn decode_into(&mut self, rx: &mut Decoder<'a>) -> Result<()>
+    where
+        Self: Sized,
+    {
+        *self = Self::decode(rx)?;
+        Ok(())
+    }
+
+    ///
+    /// Send the data to the stream and clear the buffer.
     ///
     /// Use only this method of sending (Or async variant), because it safely sends data, specifying its length at the beginning, so that nothing is lost or stuck together.
     ///
     #[cfg(feature = "net")]
-    fn receive<T: Read>(rx: &mut T) -> io::Result<Self>
+    fn send<T: Write>(&mut self, tx: &mut T) -> std::io::Result<()>
     where
-        Self: Sized;
+        Self: Sized,
+    {
+        net::send(self, tx)
+    }
 
     ///
     /// Get data from stream.
     ///
-    /// Use only this method of sending (Or syn variant), because it safely sends data, specifying its length at the beginning, so that nothing is lost or stuck together.
+    /// Use only this method of sending (Or async variant), because it safely sends data, specifying its length at the beginning, so that nothing is lost or stuck together.
     ///
-    #[cfg(feature = "net_async")]
-    async fn async_receive<T: AsyncReadExt + Unpin + AsyncRead>(rx: &mut T) -> io::Result<Self>
+    #[cfg(feature = "net")]
+    fn receive<T: Read>(rx: &mut T) -> std::io::Result<Self>
     where
-        Self: Sized;
+        Self: ConvertOwned,
+    {
+        net::receive(rx)
+    }
+}
+
+///
+/// Types that never borrow from the buffer they are decoded from.
+///
+/// Implemented automatically for every `T: for<'a> Convert<'a>`.
+///
+pub trait ConvertOwned: Sized {
+    const LAYOUT: Layout;
+
+    fn decode_owned(rx: &mut Decoder) -> Result<Self>;
+
+    fn decode_owned_into(&mut self, rx: &mut Decoder) -> Result<()>;
+}
+
+impl<T> ConvertOwned for T
+where
+    T: for<'a> Convert<'a>,
+{
+    const LAYOUT: Layout = <T as Convert<'static>>::LAYOUT;
+
+    fn decode_owned(rx: &mut Decoder) -> Result<Self> {
+        T::decode(rx)
+    }
+
+    fn decode_owned_into(&mut self, rx: &mut Decoder) -> Result<()> {
+        self.decode_into(rx)
+    }
+}
+
+///
+/// Remove the decoded bytes from the buffer, keeping the `rest` bytes that were not read.
+///
+fn consume(rx: &mut Vec<u8>, layout: Layout, rest: usize) {
+    match layout {
+        Layout::Reverse => rx.truncate(rest),
+        Layout::Forward => {
+            rx.drain(..rx.len() - rest);
+        }
+    }
 }
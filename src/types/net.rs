@@ -1,91 +1,239 @@
-use std::io::ErrorKind;
-
+use crate::types::decoder::Decoder;
+use crate::types::encoder::Encoder;
+use crate::types::error::{Error, ErrorKind};
+use crate::types::limits::DecodeLimits;
 use crate::types::varint::{from_var_int, to_var_int};
-use crate::types::Convert;
+use crate::types::{Convert, ConvertOwned};
 
 #[cfg(feature = "net")]
 use std::io::{Read, Write};
 
+#[cfg(feature = "net_async")]
+use std::future::Future;
 #[cfg(feature = "net_async")]
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
+///
+/// Largest frame accepted by `receive` and `async_receive`, 16 MiB.
+///
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+///
+/// What to do with the stream when a frame is larger than `ReceiveOptions::max_frame_size`.
+///
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub enum Oversized {
+    ///
+    /// Read and drop the frame, so the next call receives the following frame.
+    ///
+    Skip,
+
+    ///
+    /// Leave the frame unread, the connection has to be closed.
+    ///
+    Close,
+}
+
+///
+/// Per-connection settings for `receive_with` and `async_receive_with`.
+///
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct ReceiveOptions {
+    ///
+    /// Frames announcing a bigger length are rejected with `ErrorKind::FrameTooLarge` before anything is allocated.
+    ///
+    pub max_frame_size: usize,
+
+    pub on_oversized: Oversized,
+
+    ///
+    /// Limits used to decode the frame.
+    ///
+    pub limits: DecodeLimits,
+
+    ///
+    /// Replace invalid UTF-8 in owned strings instead of failing.
+    ///
+    pub lossy_utf8: bool,
+}
+
+impl Default for ReceiveOptions {
+    fn default() -> Self {
+        Self {
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            on_oversized: Oversized::Close,
+            limits: DecodeLimits::default(),
+            lossy_utf8: false,
+        }
+    }
+}
+
+impl ReceiveOptions {
+    fn check(&self, size: usize) -> Result<(), Error> {
+        if size > self.max_frame_size {
+            return Err(Error::new(ErrorKind::FrameTooLarge {
+                size,
+                max: self.max_frame_size,
+            }));
+        }
+        Ok(())
+    }
+
+    fn decode<T: ConvertOwned>(&self, chunk: &[u8]) -> std::io::Result<T> {
+        let mut rx = Decoder::with_limits(chunk, T::LAYOUT, self.limits);
+        rx.set_lossy_utf8(self.lossy_utf8);
+        Ok(T::decode_owned(&mut rx)?)
+    }
+}
+
 #[cfg(feature = "net")]
-pub fn receive<T: Convert, J: Read>(rx: &mut J) -> std::io::Result<T> {
-    let mut buf_1 = [0u8; 1];
-    let mut buf_5 = [0u8; 5];
-    let mut chunk = Vec::new();
-    let mut packet = Vec::with_capacity(32);
+pub fn receive<T: ConvertOwned, J: Read>(rx: &mut J) -> std::io::Result<T> {
+    receive_with(rx, &ReceiveOptions::default())
+}
+
+#[cfg(feature = "net")]
+pub fn receive_with<T: ConvertOwned, J: Read>(
+    rx: &mut J,
+    options: &ReceiveOptions,
+) -> std::io::Result<T> {
+    let mut buf = [0u8; 5];
+    let mut size = None;
 
     for i in 0..5 {
-        rx.read_exact(&mut buf_1)?;
-        buf_5[i] = buf_1[0];
-        if buf_5[i] & 0x80 == 0 {
-            if i == 0 {
-                chunk = vec![0u8; buf_5[i] as usize];
-                rx.read_exact(&mut chunk)?;
-                packet.push(buf_5[i]);
-                packet.extend(chunk);
-            } else {
-                chunk = vec![0u8; from_var_int(&buf_5[0..=i])?.0 as usize];
-                rx.read_exact(&mut chunk)?;
-                packet.extend(&buf_5[0..=i]);
-                packet.extend(chunk);
-            }
+        rx.read_exact(&mut buf[i..=i])?;
+        if buf[i] & 0x80 == 0 {
+            size = Some(from_var_int(&buf[0..=i])?.0 as usize);
             break;
         }
     }
-    T::from_bytes(&mut packet)
+    // The length prefix is longer than 5 bytes.
+    let size = size.ok_or(Error::new(ErrorKind::InvalidVarInt))?;
+
+    if let Err(e) = options.check(size) {
+        if options.on_oversized == Oversized::Skip {
+            std::io::copy(&mut rx.by_ref().take(size as u64), &mut std::io::sink())?;
+        }
+        return Err(e.into());
+    }
+
+    let mut chunk = vec![0u8; size];
+    rx.read_exact(&mut chunk)?;
+    options.decode(&chunk)
+}
+
+#[cfg(feature = "net_async")]
+pub async fn async_receive<T: ConvertOwned, J: AsyncReadExt + Unpin + AsyncRead>(
+    rx: &mut J,
+) -> std::io::Result<T> {
+    async_receive_with(rx, &ReceiveOptions::default()).await
 }
 
 #[cfg(feature = "net_async")]
-pub async fn async_receive<T: Convert, J: AsyncReadExt + Unpin + AsyncRead>(
+pub async fn async_receive_with<T: ConvertOwned, J: AsyncReadExt + Unpin + AsyncRead>(
     rx: &mut J,
+    options: &ReceiveOptions,
 ) -> std::io::Result<T> {
     let mut buf = [0u8; 5];
-    let mut chunk = Vec::new();
-    let mut packet = Vec::with_capacity(32);
+    let mut size = None;
 
     for i in 0..5 {
         buf[i] = rx.read_u8().await?;
         if buf[i] & 0x80 == 0 {
-            if i == 0 {
-                chunk = vec![0u8; buf[i] as usize];
-                rx.read_exact(&mut chunk).await?;
-                packet.push(buf[i]);
-                packet.extend(chunk);
-            } else {
-                chunk = vec![0u8; from_var_int(&buf[0..=i])?.0 as usize];
-                rx.read_exact(&mut chunk).await?;
-                packet.extend(&buf[0..=i]);
-                packet.extend(chunk);
-            }
+            size = Some(from_var_int(&buf[0..=i])?.0 as usize);
             break;
         }
     }
-    Ok(T::from_bytes(&mut packet)?)
+    // The length prefix is longer than 5 bytes.
+    let size = size.ok_or(Error::new(ErrorKind::InvalidVarInt))?;
+
+    if let Err(e) = options.check(size) {
+        if options.on_oversized == Oversized::Skip {
+            tokio::io::copy(&mut (&mut *rx).take(size as u64), &mut tokio::io::sink()).await?;
+        }
+        return Err(e.into());
+    }
+
+    let mut chunk = vec![0u8; size];
+    rx.read_exact(&mut chunk).await?;
+    options.decode(&chunk)
 }
 
 #[cfg(feature = "net")]
-pub fn send<T: Convert, J: Write>(data: &mut T, tx: &mut J) -> std::io::Result<()> {
-    let encoded = data.to_bytes_return();
-    if encoded.len() > u32::MAX as usize {
-        return Err(std::io::Error::from(ErrorKind::InvalidData));
-    }
-    let t = to_var_int(encoded.len() as u32);
-    tx.write_all(&t.0[..t.1 as usize])?;
-    tx.write_all(&encoded)
+pub fn send<'a, T: Convert<'a>, J: Write>(data: &mut T, tx: &mut J) -> std::io::Result<()> {
+    tx.write_all(&frame(data)?)
 }
 
 #[cfg(feature = "net_async")]
-pub async fn async_send<T: Convert, J: AsyncWriteExt + Unpin + AsyncWrite>(
+pub async fn async_send<'a, T: Convert<'a>, J: AsyncWriteExt + Unpin + AsyncWrite>(
     data: &mut T,
     tx: &mut J,
 ) -> std::io::Result<()> {
-    let encoded = data.to_bytes_return();
-    if encoded.len() > u32::MAX as usize {
-        return Err(std::io::Error::from(ErrorKind::InvalidData));
+    tx.write_all(&frame(data)?).await
+}
+
+///
+/// Length prefix followed by the encoded `data`, allocated once with the exact size.
+///
+fn frame<'a, T: Convert<'a>>(data: &T) -> std::io::Result<Vec<u8>> {
+    let len = data.encoded_len();
+    if len > u32::MAX as usize {
+        return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
+    }
+    let t = to_var_int(len as u32);
+    let mut frame = Vec::with_capacity(t.1 as usize + len);
+    frame.extend_from_slice(&t.0[..t.1 as usize]);
+    data.encode(&mut Encoder::with_layout(&mut frame, T::LAYOUT))?;
+    // A custom `encoded_len` that disagrees with `encode` would desync the stream.
+    if frame.len() != t.1 as usize + len {
+        return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
+    }
+    Ok(frame)
+}
+
+///
+/// Async networking for every `Convert` type, kept out of `Convert` so the crate builds on stable Rust.
+///
+/// Import it to call `async_send` and `async_receive` as methods.
+///
+#[cfg(feature = "net_async")]
+pub trait AsyncConvert<'a>: Convert<'a> {
+    ///
+    /// Send the data to the stream.
+    ///
+    /// Use only this method of sending (Or sync variant), because it safely sends data, specifying its length at the beginning, so that nothing is lost or stuck together.
+    ///
+    fn async_send<T: AsyncWriteExt + Unpin + AsyncWrite>(
+        &mut self,
+        tx: &mut T,
+    ) -> impl Future<Output = std::io::Result<()>>;
+
+    ///
+    /// Get data from stream.
+    ///
+    /// Use only this method of sending (Or sync variant), because it safely sends data, specifying its length at the beginning, so that nothing is lost or stuck together.
+    ///
+    fn async_receive<T: AsyncReadExt + Unpin + AsyncRead>(
+        rx: &mut T,
+    ) -> impl Future<Output = std::io::Result<Self>>
+    where
+        Self: ConvertOwned;
+}
+
+#[cfg(feature = "net_async")]
+impl<'a, C: Convert<'a>> AsyncConvert<'a> for C {
+    async fn async_send<T: AsyncWriteExt + Unpin + AsyncWrite>(
+        &mut self,
+        tx: &mut T,
+    ) -> std::io::Result<()> {
+        async_send(self, tx).await
+    }
+
+    async fn async_receive<T: AsyncReadExt + Unpin + AsyncRead>(rx: &mut T) -> std::io::Result<Self>
+    where
+        Self: ConvertOwned,
+    {
+        async_receive(rx).await
     }
-    let t = to_var_int(encoded.len() as u32);
-    tx.write_all(&t.0[..t.1 as usize]).await?;
-    tx.write_all(&encoded).await
 }
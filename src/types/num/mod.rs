@@ -1,438 +1,277 @@
-use std::io::{Error, ErrorKind};
-use std::ops::{Add, Mul};
-use std::{io, mem};
-
+use crate::types::decoder::Decoder;
+use crate::types::encoder::{Encoder, Writer};
+use crate::types::error::Result;
 use crate::types::Convert;
-use crate::{impl_net_receive, types};
 
 #[cfg(feature = "net")]
-use std::io::{Read, Write};
-
-#[cfg(feature = "net_async")]
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use std::io::Write;
 
 pub mod varint;
 
-#[inline(always)]
-fn from_le_bytes<T>(rx: &mut Vec<u8>) -> io::Result<T>
-where
-    T: Sized,
-{
-    if rx.len() < mem::size_of::<T>() {
-        return Err(io::Error::from(ErrorKind::UnexpectedEof));
-    }
-
-    let start = rx.len() - mem::size_of::<T>();
-    let result;
+impl<'a> Convert<'a> for u8 {
+    const MAX_SIZE: Option<usize> = Some(1);
 
-    unsafe {
-        let ptr = rx.as_ptr().add(start) as *const T;
-        result = ptr.read_unaligned();
-    }
-    rx.truncate(start);
-    Ok(result)
-}
-
-impl Convert for u8 {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
         tx.push(*self)
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        vec![*self]
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        rx.read_u8()
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        if let Some(u) = rx.pop() {
-            Ok(u)
-        } else {
-            Err(Error::from(ErrorKind::InvalidData))
-        }
+    fn encoded_len(&self) -> usize {
+        1
     }
 
     #[cfg(feature = "net")]
-    fn send<T: Write>(&mut self, tx: &mut T) -> io::Result<()> {
-        tx.write_all(&vec![1, *self])
-    }
-    #[cfg(feature = "net_async")]
-    async fn async_send<T: AsyncWriteExt + Unpin + AsyncWrite>(
-        &mut self,
-        tx: &mut T,
-    ) -> io::Result<()> {
-        tx.write_all(&vec![1, *self]).await
+    fn send<T: Write>(&mut self, tx: &mut T) -> std::io::Result<()> {
+        tx.write_all(&[1, *self])
     }
-
-    impl_net_receive!();
 }
-impl Convert for u16 {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        tx.extend_from_slice(&self.to_le_bytes())
+impl<'a> Convert<'a> for u16 {
+    const MAX_SIZE: Option<usize> = Some(2);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        tx.write(&self.to_le_bytes())
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        self.to_le_bytes().to_vec()
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        Ok(Self::from_le_bytes(rx.read_array()?))
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        from_le_bytes(rx)
+    fn encoded_len(&self) -> usize {
+        2
     }
 
     #[cfg(feature = "net")]
-    fn send<T: Write>(&mut self, tx: &mut T) -> io::Result<()> {
-        tx.write_all(&vec![2])?;
+    fn send<T: Write>(&mut self, tx: &mut T) -> std::io::Result<()> {
+        tx.write_all(&[2])?;
         tx.write_all(&self.to_le_bytes())
     }
-    #[cfg(feature = "net_async")]
-    async fn async_send<T: AsyncWriteExt + Unpin + AsyncWrite>(
-        &mut self,
-        tx: &mut T,
-    ) -> io::Result<()> {
-        tx.write_all(&vec![2]).await?;
-        tx.write_all(&self.to_le_bytes()).await
-    }
-
-    impl_net_receive!();
 }
-impl Convert for u32 {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        tx.extend_from_slice(&self.to_le_bytes())
+impl<'a> Convert<'a> for u32 {
+    const MAX_SIZE: Option<usize> = Some(4);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        tx.write(&self.to_le_bytes())
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        self.to_le_bytes().to_vec()
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        Ok(Self::from_le_bytes(rx.read_array()?))
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        from_le_bytes(rx)
+    fn encoded_len(&self) -> usize {
+        4
     }
 
     #[cfg(feature = "net")]
-    fn send<T: Write>(&mut self, tx: &mut T) -> io::Result<()> {
-        tx.write_all(&vec![4])?;
+    fn send<T: Write>(&mut self, tx: &mut T) -> std::io::Result<()> {
+        tx.write_all(&[4])?;
         tx.write_all(&self.to_le_bytes())
     }
-    #[cfg(feature = "net_async")]
-    async fn async_send<T: AsyncWriteExt + Unpin + AsyncWrite>(
-        &mut self,
-        tx: &mut T,
-    ) -> io::Result<()> {
-        tx.write_all(&vec![4]).await?;
-        tx.write_all(&self.to_le_bytes()).await
-    }
-
-    impl_net_receive!();
 }
-impl Convert for u64 {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        tx.extend_from_slice(&self.to_le_bytes())
+impl<'a> Convert<'a> for u64 {
+    const MAX_SIZE: Option<usize> = Some(8);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        tx.write(&self.to_le_bytes())
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        self.to_le_bytes().to_vec()
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        Ok(Self::from_le_bytes(rx.read_array()?))
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        from_le_bytes(rx)
+    fn encoded_len(&self) -> usize {
+        8
     }
 
     #[cfg(feature = "net")]
-    fn send<T: Write>(&mut self, tx: &mut T) -> io::Result<()> {
-        tx.write_all(&vec![8])?;
+    fn send<T: Write>(&mut self, tx: &mut T) -> std::io::Result<()> {
+        tx.write_all(&[8])?;
         tx.write_all(&self.to_le_bytes())
     }
-    #[cfg(feature = "net_async")]
-    async fn async_send<T: AsyncWriteExt + Unpin + AsyncWrite>(
-        &mut self,
-        tx: &mut T,
-    ) -> io::Result<()> {
-        tx.write_all(&vec![8]).await?;
-        tx.write_all(&self.to_le_bytes()).await
-    }
-
-    impl_net_receive!();
 }
-impl Convert for u128 {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        tx.extend_from_slice(&self.to_le_bytes())
+impl<'a> Convert<'a> for u128 {
+    const MAX_SIZE: Option<usize> = Some(16);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        tx.write(&self.to_le_bytes())
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        self.to_le_bytes().to_vec()
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        Ok(Self::from_le_bytes(rx.read_array()?))
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        from_le_bytes(rx)
+    fn encoded_len(&self) -> usize {
+        16
     }
 
     #[cfg(feature = "net")]
-    fn send<T: Write>(&mut self, tx: &mut T) -> io::Result<()> {
-        tx.write_all(&vec![16])?;
+    fn send<T: Write>(&mut self, tx: &mut T) -> std::io::Result<()> {
+        tx.write_all(&[16])?;
         tx.write_all(&self.to_le_bytes())
     }
-    #[cfg(feature = "net_async")]
-    async fn async_send<T: AsyncWriteExt + Unpin + AsyncWrite>(
-        &mut self,
-        tx: &mut T,
-    ) -> io::Result<()> {
-        tx.write_all(&vec![16]).await?;
-        tx.write_all(&self.to_le_bytes()).await
-    }
-
-    impl_net_receive!();
 }
 
-impl Convert for i8 {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
+impl<'a> Convert<'a> for i8 {
+    const MAX_SIZE: Option<usize> = Some(1);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
         tx.push(*self as u8)
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        vec![*self as u8]
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        Ok(rx.read_u8()? as i8)
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        if let Some(i) = rx.pop() {
-            Ok(i as i8)
-        } else {
-            Err(Error::from(ErrorKind::InvalidData))
-        }
+    fn encoded_len(&self) -> usize {
+        1
     }
 
     #[cfg(feature = "net")]
-    fn send<T: Write>(&mut self, tx: &mut T) -> io::Result<()> {
-        tx.write_all(&vec![1, *self as u8])
+    fn send<T: Write>(&mut self, tx: &mut T) -> std::io::Result<()> {
+        tx.write_all(&[1, *self as u8])
     }
-    #[cfg(feature = "net_async")]
-    async fn async_send<T: AsyncWriteExt + Unpin + AsyncWrite>(
-        &mut self,
-        tx: &mut T,
-    ) -> io::Result<()> {
-        tx.write_all(&vec![1, *self as u8]).await
-    }
-
-    impl_net_receive!();
 }
-impl Convert for i16 {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        tx.extend_from_slice(&self.to_le_bytes())
+impl<'a> Convert<'a> for i16 {
+    const MAX_SIZE: Option<usize> = Some(2);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        tx.write(&self.to_le_bytes())
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        self.to_le_bytes().to_vec()
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        Ok(Self::from_le_bytes(rx.read_array()?))
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        from_le_bytes(rx)
+    fn encoded_len(&self) -> usize {
+        2
     }
 
     #[cfg(feature = "net")]
-    fn send<T: Write>(&mut self, tx: &mut T) -> io::Result<()> {
-        tx.write_all(&vec![2])?;
+    fn send<T: Write>(&mut self, tx: &mut T) -> std::io::Result<()> {
+        tx.write_all(&[2])?;
         tx.write_all(&self.to_le_bytes())
     }
-    #[cfg(feature = "net_async")]
-    async fn async_send<T: AsyncWriteExt + Unpin + AsyncWrite>(
-        &mut self,
-        tx: &mut T,
-    ) -> io::Result<()> {
-        tx.write_all(&vec![2]).await?;
-        tx.write_all(&self.to_le_bytes()).await
-    }
-
-    impl_net_receive!();
 }
-impl Convert for i32 {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        tx.extend_from_slice(&self.to_le_bytes())
+impl<'a> Convert<'a> for i32 {
+    const MAX_SIZE: Option<usize> = Some(4);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        tx.write(&self.to_le_bytes())
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        self.to_le_bytes().to_vec()
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        Ok(Self::from_le_bytes(rx.read_array()?))
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        from_le_bytes(rx)
+    fn encoded_len(&self) -> usize {
+        4
     }
 
     #[cfg(feature = "net")]
-    fn send<T: Write>(&mut self, tx: &mut T) -> io::Result<()> {
-        tx.write_all(&vec![4])?;
+    fn send<T: Write>(&mut self, tx: &mut T) -> std::io::Result<()> {
+        tx.write_all(&[4])?;
         tx.write_all(&self.to_le_bytes())
     }
-    #[cfg(feature = "net_async")]
-    async fn async_send<T: AsyncWriteExt + Unpin + AsyncWrite>(
-        &mut self,
-        tx: &mut T,
-    ) -> io::Result<()> {
-        tx.write_all(&vec![4]).await?;
-        tx.write_all(&self.to_le_bytes()).await
-    }
-
-    impl_net_receive!();
 }
-impl Convert for i64 {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        tx.extend_from_slice(&self.to_le_bytes())
+impl<'a> Convert<'a> for i64 {
+    const MAX_SIZE: Option<usize> = Some(8);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        tx.write(&self.to_le_bytes())
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        self.to_le_bytes().to_vec()
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        Ok(Self::from_le_bytes(rx.read_array()?))
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        from_le_bytes(rx)
+    fn encoded_len(&self) -> usize {
+        8
     }
 
     #[cfg(feature = "net")]
-    fn send<T: Write>(&mut self, tx: &mut T) -> io::Result<()> {
-        tx.write_all(&vec![8])?;
+    fn send<T: Write>(&mut self, tx: &mut T) -> std::io::Result<()> {
+        tx.write_all(&[8])?;
         tx.write_all(&self.to_le_bytes())
     }
-    #[cfg(feature = "net_async")]
-    async fn async_send<T: AsyncWriteExt + Unpin + AsyncWrite>(
-        &mut self,
-        tx: &mut T,
-    ) -> io::Result<()> {
-        tx.write_all(&vec![8]).await?;
-        tx.write_all(&self.to_le_bytes()).await
-    }
-
-    impl_net_receive!();
 }
-impl Convert for i128 {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        tx.extend_from_slice(&self.to_le_bytes())
+impl<'a> Convert<'a> for i128 {
+    const MAX_SIZE: Option<usize> = Some(16);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        tx.write(&self.to_le_bytes())
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        self.to_le_bytes().to_vec()
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        Ok(Self::from_le_bytes(rx.read_array()?))
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        from_le_bytes(rx)
+    fn encoded_len(&self) -> usize {
+        16
     }
 
     #[cfg(feature = "net")]
-    fn send<T: Write>(&mut self, tx: &mut T) -> io::Result<()> {
-        tx.write_all(&vec![16])?;
+    fn send<T: Write>(&mut self, tx: &mut T) -> std::io::Result<()> {
+        tx.write_all(&[16])?;
         tx.write_all(&self.to_le_bytes())
     }
-    #[cfg(feature = "net_async")]
-    async fn async_send<T: AsyncWriteExt + Unpin + AsyncWrite>(
-        &mut self,
-        tx: &mut T,
-    ) -> io::Result<()> {
-        tx.write_all(&vec![16]).await?;
-        tx.write_all(&self.to_le_bytes()).await
-    }
-
-    impl_net_receive!();
 }
 
-impl Convert for usize {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        tx.extend_from_slice(&(*self as u64).to_le_bytes())
+impl<'a> Convert<'a> for usize {
+    const MAX_SIZE: Option<usize> = Some(8);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        tx.write(&(*self as u64).to_le_bytes())
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        (*self as u64).to_le_bytes().to_vec()
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        Ok(u64::from_le_bytes(rx.read_array()?) as Self)
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        Ok(from_le_bytes::<u64>(rx)? as Self)
+    fn encoded_len(&self) -> usize {
+        8
     }
 
     #[cfg(feature = "net")]
-    fn send<T: Write>(&mut self, tx: &mut T) -> io::Result<()> {
-        tx.write_all(&vec![8])?;
+    fn send<T: Write>(&mut self, tx: &mut T) -> std::io::Result<()> {
+        tx.write_all(&[8])?;
         tx.write_all(&((*self as u64).to_le_bytes()))
     }
-
-    #[cfg(feature = "net_async")]
-    async fn async_send<T: AsyncWriteExt + Unpin + AsyncWrite>(
-        &mut self,
-        tx: &mut T,
-    ) -> io::Result<()> {
-        tx.write_all(&vec![8]).await?;
-        tx.write_all(&((*self as u64).to_le_bytes())).await
-    }
-
-    #[cfg(feature = "net")]
-    fn receive<T: Read>(rx: &mut T) -> io::Result<Self> {
-        Ok(types::net::receive::<u64, T>(rx)? as Self)
-    }
-
-    #[cfg(feature = "net_async")]
-    async fn async_receive<T: AsyncReadExt + Unpin + AsyncRead>(rx: &mut T) -> io::Result<Self> {
-        Ok(types::net::async_receive::<u64, T>(rx).await? as Self)
-    }
 }
-impl Convert for isize {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        tx.extend_from_slice(&(*self as i64).to_le_bytes())
+impl<'a> Convert<'a> for isize {
+    const MAX_SIZE: Option<usize> = Some(8);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        tx.write(&(*self as i64).to_le_bytes())
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        (*self as i64).to_le_bytes().to_vec()
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        Ok(i64::from_le_bytes(rx.read_array()?) as Self)
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        Ok(from_le_bytes::<i64>(rx)? as Self)
+    fn encoded_len(&self) -> usize {
+        8
     }
 
     #[cfg(feature = "net")]
-    fn send<T: Write>(&mut self, tx: &mut T) -> io::Result<()> {
-        tx.write_all(&vec![8])?;
+    fn send<T: Write>(&mut self, tx: &mut T) -> std::io::Result<()> {
+        tx.write_all(&[8])?;
         tx.write_all(&((*self as i64).to_le_bytes()))
     }
-
-    #[cfg(feature = "net_async")]
-    async fn async_send<T: AsyncWriteExt + Unpin + AsyncWrite>(
-        &mut self,
-        tx: &mut T,
-    ) -> io::Result<()> {
-        tx.write_all(&vec![8]).await?;
-        tx.write_all(&((*self as i64).to_le_bytes())).await
-    }
-
-    #[cfg(feature = "net")]
-    fn receive<T: Read>(rx: &mut T) -> io::Result<Self> {
-        Ok(types::net::receive::<i64, T>(rx)? as Self)
-    }
-
-    #[cfg(feature = "net_async")]
-    async fn async_receive<T: AsyncReadExt + Unpin + AsyncRead>(rx: &mut T) -> io::Result<Self> {
-        Ok(types::net::async_receive::<i64, T>(rx).await? as Self)
-    }
 }
 
-impl Convert for f32 {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        tx.extend_from_slice(&self.to_le_bytes())
+impl<'a> Convert<'a> for f32 {
+    const MAX_SIZE: Option<usize> = Some(4);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        tx.write(&self.to_le_bytes())
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        self.to_le_bytes().to_vec()
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        Ok(Self::from_le_bytes(rx.read_array()?))
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        from_le_bytes(rx)
+    fn encoded_len(&self) -> usize {
+        4
     }
 
     #[cfg(feature = "net")]
-    fn send<T: Write>(&mut self, tx: &mut T) -> io::Result<()> {
-        tx.write_all(&vec![4])?;
+    fn send<T: Write>(&mut self, tx: &mut T) -> std::io::Result<()> {
+        tx.write_all(&[4])?;
         tx.write_all(&self.to_le_bytes())
     }
-    #[cfg(feature = "net_async")]
-    async fn async_send<T: AsyncWriteExt + Unpin + AsyncWrite>(
-        &mut self,
-        tx: &mut T,
-    ) -> io::Result<()> {
-        tx.write_all(&vec![4]).await?;
-        tx.write_all(&self.to_le_bytes()).await
-    }
-
-    impl_net_receive!();
 }
-impl Convert for f64 {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        tx.extend_from_slice(&self.to_le_bytes())
+impl<'a> Convert<'a> for f64 {
+    const MAX_SIZE: Option<usize> = Some(8);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        tx.write(&self.to_le_bytes())
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        self.to_le_bytes().to_vec()
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        Ok(Self::from_le_bytes(rx.read_array()?))
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        from_le_bytes(rx)
+    fn encoded_len(&self) -> usize {
+        8
     }
 
     #[cfg(feature = "net")]
-    fn send<T: Write>(&mut self, tx: &mut T) -> io::Result<()> {
-        tx.write_all(&vec![8])?;
+    fn send<T: Write>(&mut self, tx: &mut T) -> std::io::Result<()> {
+        tx.write_all(&[8])?;
         tx.write_all(&self.to_le_bytes())
     }
-    #[cfg(feature = "net_async")]
-    async fn async_send<T: AsyncWriteExt + Unpin + AsyncWrite>(
-        &mut self,
-        tx: &mut T,
-    ) -> io::Result<()> {
-        tx.write_all(&vec![8]).await?;
-        tx.write_all(&self.to_le_bytes()).await
-    }
-
-    impl_net_receive!();
 }
@@ -1,14 +1,8 @@
-use std::io;
-
-use crate::types::varint::{from_var_int_rev, to_var_int};
+use crate::types::decoder::Decoder;
+use crate::types::encoder::{Encoder, Writer};
+use crate::types::error::Result;
+use crate::types::varint::var_int_len;
 use crate::types::Convert;
-use crate::{impl_net, types};
-
-#[cfg(feature = "net")]
-use std::io::{Read, Write};
-
-#[cfg(feature = "net_async")]
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
 ///
 /// A number with variable length.
@@ -23,23 +17,16 @@ use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 #[cfg_attr(feature = "partial_eq", derive(PartialEq))]
 pub struct VarInt(pub u32);
 
-impl Convert for VarInt {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        let t = to_var_int(self.0);
-        let mut t = t.0[..t.1 as usize].to_vec();
-        t.reverse();
-        tx.extend_from_slice(&t);
+impl<'a> Convert<'a> for VarInt {
+    const MAX_SIZE: Option<usize> = Some(5);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        tx.write_var_int(self.0)
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        let mut tx = vec![];
-        &self.to_bytes(&mut tx);
-        tx
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        Ok(Self(rx.read_var_int()?))
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        let x = from_var_int_rev(rx)?;
-        rx.truncate(rx.len() - x.1);
-        Ok(Self(x.0))
+    fn encoded_len(&self) -> usize {
+        var_int_len(self.0)
     }
-
-    impl_net!();
 }
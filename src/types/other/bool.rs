@@ -1,42 +1,30 @@
-use std::io;
-use std::io::{Error, ErrorKind};
-
+use crate::types::decoder::Decoder;
+use crate::types::encoder::{Encoder, Writer};
+use crate::types::error::{Error, ErrorKind, Result};
 use crate::types::Convert;
-use crate::{impl_net_receive, types};
 
 #[cfg(feature = "net")]
-use std::io::{Read, Write};
+use std::io::Write;
 
-#[cfg(feature = "net_async")]
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+impl<'a> Convert<'a> for bool {
+    const MAX_SIZE: Option<usize> = Some(1);
 
-impl Convert for bool {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
         tx.push(*self as u8)
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        vec![*self as u8]
-    }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        if let Some(u) = rx.pop() {
-            Ok(u == 1)
-        } else {
-            Err(Error::from(ErrorKind::InvalidData))
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        match rx.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            u => Err(Error::new(ErrorKind::InvalidBool(u))),
         }
     }
-
-    #[cfg(feature = "net")]
-    fn send<T: Write>(&mut self, tx: &mut T) -> io::Result<()> {
-        tx.write_all(&vec![1, *self as u8])
+    fn encoded_len(&self) -> usize {
+        1
     }
 
-    #[cfg(feature = "net_async")]
-    async fn async_send<T: AsyncWriteExt + Unpin + AsyncWrite>(
-        &mut self,
-        tx: &mut T,
-    ) -> io::Result<()> {
-        tx.write_all(&vec![1, *self as u8]).await
+    #[cfg(feature = "net")]
+    fn send<T: Write>(&mut self, tx: &mut T) -> std::io::Result<()> {
+        tx.write_all(&[1, *self as u8])
     }
-
-    impl_net_receive!();
 }
@@ -0,0 +1,40 @@
+use core::marker::PhantomData;
+
+use crate::types::decoder::Decoder;
+use crate::types::encoder::{Encoder, Writer};
+use crate::types::error::Result;
+use crate::types::Convert;
+
+///
+/// Encoded as zero bytes.
+///
+impl<'a> Convert<'a> for () {
+    const MAX_SIZE: Option<usize> = Some(0);
+
+    fn encode<W: Writer>(&self, _tx: &mut Encoder<W>) -> Result<()> {
+        Ok(())
+    }
+    fn decode(_rx: &mut Decoder<'a>) -> Result<Self> {
+        Ok(())
+    }
+    fn encoded_len(&self) -> usize {
+        0
+    }
+}
+
+///
+/// Encoded as zero bytes, `T` does not have to be convertible.
+///
+impl<'a, T: ?Sized> Convert<'a> for PhantomData<T> {
+    const MAX_SIZE: Option<usize> = Some(0);
+
+    fn encode<W: Writer>(&self, _tx: &mut Encoder<W>) -> Result<()> {
+        Ok(())
+    }
+    fn decode(_rx: &mut Decoder<'a>) -> Result<Self> {
+        Ok(PhantomData)
+    }
+    fn encoded_len(&self) -> usize {
+        0
+    }
+}
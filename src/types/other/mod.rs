@@ -1,3 +1,4 @@
 pub mod bool;
+mod empty;
 pub mod option;
 mod tuple;
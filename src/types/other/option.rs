@@ -1,38 +1,42 @@
-use std::io;
-use std::io::{Error, ErrorKind};
+use crate::types::decoder::Decoder;
+use crate::types::encoder::{Encoder, Writer};
+use crate::types::error::{Error, ErrorKind, Result};
+use crate::types::{size, Convert};
 
-use crate::types::Convert;
-use crate::{impl_net, types};
+impl<'a, T: Convert<'a>> Convert<'a> for Option<T> {
+    const MAX_SIZE: Option<usize> = size::sum(&[Some(1), T::MAX_SIZE]);
 
-#[cfg(feature = "net")]
-use std::io::{Read, Write};
-
-#[cfg(feature = "net_async")]
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
-
-impl<T: Convert> Convert for Option<T> {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
         match self {
             None => tx.push(0),
-            Some(some) => {
-                some.to_bytes(tx);
-                tx.push(1);
-            }
+            Some(some) => tx.prefixed(|tx| tx.push(1), |tx| some.encode(tx)),
         }
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        let mut tx = vec![];
-        &self.to_bytes(&mut tx);
-        tx
-    }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        let x = rx.split_off(rx.len() - 1)[0];
-        Ok(match x {
-            1 => Some(T::from_bytes(rx)?),
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        Ok(match rx.read_u8()? {
+            1 => Some(T::decode(rx)?),
             0 => None,
-            _ => return Err(Error::from(ErrorKind::InvalidData)),
+            tag => return Err(Error::new(ErrorKind::UnknownTag(tag as u32))),
         })
     }
-
-    impl_net!();
+    fn decode_into(&mut self, rx: &mut Decoder<'a>) -> Result<()> {
+        match (rx.read_u8()?, self) {
+            (1, Some(some)) => some.decode_into(rx),
+            (1, this) => {
+                *this = Some(T::decode(rx)?);
+                Ok(())
+            }
+            (0, this) => {
+                *this = None;
+                Ok(())
+            }
+            (tag, _) => Err(Error::new(ErrorKind::UnknownTag(tag as u32))),
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        match self {
+            None => 1,
+            Some(some) => 1 + some.encoded_len(),
+        }
+    }
 }
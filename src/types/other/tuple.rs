@@ -1,187 +1,228 @@
-use std::io;
+use crate::types::decoder::Decoder;
+use crate::types::encoder::{Encoder, Writer};
+use crate::types::error::Result;
+use crate::types::layout::Layout;
+use crate::types::{size, Convert};
 
-use crate::types::Convert;
-use crate::{impl_net, types};
-
-#[cfg(feature = "net")]
-use std::io::{Read, Write};
-
-#[cfg(feature = "net_async")]
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
-
-impl<A> Convert for (A,)
+impl<'a, A> Convert<'a> for (A,)
 where
-    A: Convert,
+    A: Convert<'a>,
 {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        self.0.to_bytes(tx);
+    const MAX_SIZE: Option<usize> = size::sum(&[A::MAX_SIZE]);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        self.0.encode(tx)
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        let mut tx = Vec::new();
-        self.0.to_bytes(&mut tx);
-        tx
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        Ok((A::decode(rx)?,))
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        Ok((A::from_bytes(rx)?,))
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len()
     }
-
-    impl_net!();
 }
 
-impl<A, B> Convert for (A, B)
+impl<'a, A, B> Convert<'a> for (A, B)
 where
-    A: Convert,
-    B: Convert,
+    A: Convert<'a>,
+    B: Convert<'a>,
 {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        self.1.to_bytes(tx);
-        self.0.to_bytes(tx);
-    }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        let mut tx = Vec::new();
-        self.1.to_bytes(&mut tx);
-        self.0.to_bytes(&mut tx);
-        tx
-    }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        Ok((A::from_bytes(rx)?, B::from_bytes(rx)?))
-    }
+    const MAX_SIZE: Option<usize> = size::sum(&[A::MAX_SIZE, B::MAX_SIZE]);
 
-    impl_net!();
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        match tx.layout() {
+            Layout::Reverse => {
+                self.1.encode(tx)?;
+                self.0.encode(tx)
+            }
+            Layout::Forward => {
+                self.0.encode(tx)?;
+                self.1.encode(tx)
+            }
+        }
+    }
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        Ok((A::decode(rx)?, B::decode(rx)?))
+    }
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len() + self.1.encoded_len()
+    }
 }
 
-impl<A, B, C> Convert for (A, B, C)
+impl<'a, A, B, C> Convert<'a> for (A, B, C)
 where
-    A: Convert,
-    B: Convert,
-    C: Convert,
+    A: Convert<'a>,
+    B: Convert<'a>,
+    C: Convert<'a>,
 {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        self.2.to_bytes(tx);
-        self.1.to_bytes(tx);
-        self.0.to_bytes(tx);
-    }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        let mut tx = Vec::new();
-        self.2.to_bytes(&mut tx);
-        self.1.to_bytes(&mut tx);
-        self.0.to_bytes(&mut tx);
-        tx
-    }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        Ok((A::from_bytes(rx)?, B::from_bytes(rx)?, C::from_bytes(rx)?))
-    }
+    const MAX_SIZE: Option<usize> = size::sum(&[A::MAX_SIZE, B::MAX_SIZE, C::MAX_SIZE]);
 
-    impl_net!();
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        match tx.layout() {
+            Layout::Reverse => {
+                self.2.encode(tx)?;
+                self.1.encode(tx)?;
+                self.0.encode(tx)
+            }
+            Layout::Forward => {
+                self.0.encode(tx)?;
+                self.1.encode(tx)?;
+                self.2.encode(tx)
+            }
+        }
+    }
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        Ok((A::decode(rx)?, B::decode(rx)?, C::decode(rx)?))
+    }
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len() + self.1.encoded_len() + self.2.encoded_len()
+    }
 }
 
-impl<A, B, C, D> Convert for (A, B, C, D)
+impl<'a, A, B, C, D> Convert<'a> for (A, B, C, D)
 where
-    A: Convert,
-    B: Convert,
-    C: Convert,
-    D: Convert,
+    A: Convert<'a>,
+    B: Convert<'a>,
+    C: Convert<'a>,
+    D: Convert<'a>,
 {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        self.3.to_bytes(tx);
-        self.2.to_bytes(tx);
-        self.1.to_bytes(tx);
-        self.0.to_bytes(tx);
-    }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        let mut tx = Vec::new();
-        self.3.to_bytes(&mut tx);
-        self.2.to_bytes(&mut tx);
-        self.1.to_bytes(&mut tx);
-        self.0.to_bytes(&mut tx);
-        tx
-    }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
+    const MAX_SIZE: Option<usize> =
+        size::sum(&[A::MAX_SIZE, B::MAX_SIZE, C::MAX_SIZE, D::MAX_SIZE]);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        match tx.layout() {
+            Layout::Reverse => {
+                self.3.encode(tx)?;
+                self.2.encode(tx)?;
+                self.1.encode(tx)?;
+                self.0.encode(tx)
+            }
+            Layout::Forward => {
+                self.0.encode(tx)?;
+                self.1.encode(tx)?;
+                self.2.encode(tx)?;
+                self.3.encode(tx)
+            }
+        }
+    }
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
         Ok((
-            A::from_bytes(rx)?,
-            B::from_bytes(rx)?,
-            C::from_bytes(rx)?,
-            D::from_bytes(rx)?,
+            A::decode(rx)?,
+            B::decode(rx)?,
+            C::decode(rx)?,
+            D::decode(rx)?,
         ))
     }
-
-    impl_net!();
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len() + self.1.encoded_len() + self.2.encoded_len() + self.3.encoded_len()
+    }
 }
 
-impl<A, B, C, D, E> Convert for (A, B, C, D, E)
+impl<'a, A, B, C, D, E> Convert<'a> for (A, B, C, D, E)
 where
-    A: Convert,
-    B: Convert,
-    C: Convert,
-    D: Convert,
-    E: Convert,
+    A: Convert<'a>,
+    B: Convert<'a>,
+    C: Convert<'a>,
+    D: Convert<'a>,
+    E: Convert<'a>,
 {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        self.4.to_bytes(tx);
-        self.3.to_bytes(tx);
-        self.2.to_bytes(tx);
-        self.1.to_bytes(tx);
-        self.0.to_bytes(tx);
-    }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        let mut tx = Vec::new();
-        self.4.to_bytes(&mut tx);
-        self.3.to_bytes(&mut tx);
-        self.2.to_bytes(&mut tx);
-        self.1.to_bytes(&mut tx);
-        self.0.to_bytes(&mut tx);
-        tx
-    }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
+    const MAX_SIZE: Option<usize> = size::sum(&[
+        A::MAX_SIZE,
+        B::MAX_SIZE,
+        C::MAX_SIZE,
+        D::MAX_SIZE,
+        E::MAX_SIZE,
+    ]);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        match tx.layout() {
+            Layout::Reverse => {
+                self.4.encode(tx)?;
+                self.3.encode(tx)?;
+                self.2.encode(tx)?;
+                self.1.encode(tx)?;
+                self.0.encode(tx)
+            }
+            Layout::Forward => {
+                self.0.encode(tx)?;
+                self.1.encode(tx)?;
+                self.2.encode(tx)?;
+                self.3.encode(tx)?;
+                self.4.encode(tx)
+            }
+        }
+    }
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
         Ok((
-            A::from_bytes(rx)?,
-            B::from_bytes(rx)?,
-            C::from_bytes(rx)?,
-            D::from_bytes(rx)?,
-            E::from_bytes(rx)?,
+            A::decode(rx)?,
+            B::decode(rx)?,
+            C::decode(rx)?,
+            D::decode(rx)?,
+            E::decode(rx)?,
         ))
     }
-
-    impl_net!();
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len()
+            + self.1.encoded_len()
+            + self.2.encoded_len()
+            + self.3.encoded_len()
+            + self.4.encoded_len()
+    }
 }
 
-impl<A, B, C, D, E, F> Convert for (A, B, C, D, E, F)
+impl<'a, A, B, C, D, E, F> Convert<'a> for (A, B, C, D, E, F)
 where
-    A: Convert,
-    B: Convert,
-    C: Convert,
-    D: Convert,
-    E: Convert,
-    F: Convert,
+    A: Convert<'a>,
+    B: Convert<'a>,
+    C: Convert<'a>,
+    D: Convert<'a>,
+    E: Convert<'a>,
+    F: Convert<'a>,
 {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        self.5.to_bytes(tx);
-        self.4.to_bytes(tx);
-        self.3.to_bytes(tx);
-        self.2.to_bytes(tx);
-        self.1.to_bytes(tx);
-        self.0.to_bytes(tx);
-    }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        let mut tx = Vec::new();
-        self.5.to_bytes(&mut tx);
-        self.4.to_bytes(&mut tx);
-        self.3.to_bytes(&mut tx);
-        self.2.to_bytes(&mut tx);
-        self.1.to_bytes(&mut tx);
-        self.0.to_bytes(&mut tx);
-        tx
-    }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
+    const MAX_SIZE: Option<usize> = size::sum(&[
+        A::MAX_SIZE,
+        B::MAX_SIZE,
+        C::MAX_SIZE,
+        D::MAX_SIZE,
+        E::MAX_SIZE,
+        F::MAX_SIZE,
+    ]);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        match tx.layout() {
+            Layout::Reverse => {
+                self.5.encode(tx)?;
+                self.4.encode(tx)?;
+                self.3.encode(tx)?;
+                self.2.encode(tx)?;
+                self.1.encode(tx)?;
+                self.0.encode(tx)
+            }
+            Layout::Forward => {
+                self.0.encode(tx)?;
+                self.1.encode(tx)?;
+                self.2.encode(tx)?;
+                self.3.encode(tx)?;
+                self.4.encode(tx)?;
+                self.5.encode(tx)
+            }
+        }
+    }
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
         Ok((
-            A::from_bytes(rx)?,
-            B::from_bytes(rx)?,
-            C::from_bytes(rx)?,
-            D::from_bytes(rx)?,
-            E::from_bytes(rx)?,
-            F::from_bytes(rx)?,
+            A::decode(rx)?,
+            B::decode(rx)?,
+            C::decode(rx)?,
+            D::decode(rx)?,
+            E::decode(rx)?,
+            F::decode(rx)?,
         ))
     }
-
-    impl_net!();
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len()
+            + self.1.encoded_len()
+            + self.2.encoded_len()
+            + self.3.encoded_len()
+            + self.4.encoded_len()
+            + self.5.encoded_len()
+    }
 }
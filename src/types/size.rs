@@ -0,0 +1,45 @@
+///
+/// Size of values laid out one after another.
+///
+pub const fn sum(sizes: &[Option<usize>]) -> Option<usize> {
+    let mut total = 0usize;
+    let mut i = 0;
+    while i < sizes.len() {
+        total = match sizes[i] {
+            Some(size) => match total.checked_add(size) {
+                Some(total) => total,
+                None => return None,
+            },
+            None => return None,
+        };
+        i += 1;
+    }
+    Some(total)
+}
+
+///
+/// Size of one value out of several (e.g. the variants of an enum).
+///
+pub const fn max(sizes: &[Option<usize>]) -> Option<usize> {
+    let mut largest = 0usize;
+    let mut i = 0;
+    while i < sizes.len() {
+        match sizes[i] {
+            Some(size) if size > largest => largest = size,
+            Some(_) => {}
+            None => return None,
+        }
+        i += 1;
+    }
+    Some(largest)
+}
+
+///
+/// Size of `n` values of the same type.
+///
+pub const fn repeat(size: Option<usize>, n: usize) -> Option<usize> {
+    match size {
+        Some(size) => size.checked_mul(n),
+        None => None,
+    }
+}
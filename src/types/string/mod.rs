@@ -1,15 +1,11 @@
-use std::io;
-use std::os::unix::prelude::OsStrExt;
+use alloc::borrow::Cow;
+use alloc::string::String;
 
-use crate::types::varint::{from_var_int_rev, to_var_int};
-use crate::types::Convert;
-use crate::{impl_net, types};
-
-#[cfg(feature = "net")]
-use std::io::{Read, Write};
-
-#[cfg(feature = "net_async")]
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use crate::types::decoder::Decoder;
+use crate::types::encoder::{Encoder, Writer};
+use crate::types::error::{Error, ErrorKind, Result};
+use crate::types::varint::var_int_len;
+use crate::types::{size, Convert};
 
 ///
 /// String prefixed with u8.
@@ -55,133 +51,196 @@ pub struct MediumString(pub String);
 #[cfg_attr(feature = "partial_eq", derive(PartialEq))]
 pub struct LongString(pub String);
 
-impl Convert for TinyString {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        if self.0.len() < 255 {
-            tx.extend_from_slice(self.0.as_bytes());
-            tx.push(self.0.len() as u8);
-        } else {
-            tx.extend_from_slice(&self.0.as_bytes()[0..255]);
-            tx.push(255);
-        }
+impl<'a> Convert<'a> for TinyString {
+    const MAX_SIZE: Option<usize> = Some(1 + u8::MAX as usize);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        let len = fit_str(tx, &self.0, 255)?;
+        tx.prefixed(
+            |tx| tx.push(len as u8),
+            |tx| tx.write(&self.0.as_bytes()[..len]),
+        )
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        let mut tx = vec![];
-        &self.to_bytes(&mut tx);
-        tx
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        let size = u8::decode(rx)?;
+        Ok(Self(decode_string(rx, size as usize)?))
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        let size = u8::from_bytes(rx)?;
-        Ok(Self(
-            String::from_utf8_lossy(&rx.split_off(rx.len() - size as usize)).to_string(),
-        ))
+    fn decode_into(&mut self, rx: &mut Decoder<'a>) -> Result<()> {
+        let size = u8::decode(rx)?;
+        decode_string_into(rx, &mut self.0, size as usize)
+    }
+    fn encoded_len(&self) -> usize {
+        1 + self.0.len()
     }
-
-    impl_net!();
 }
 
-impl Convert for ShortString {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        if self.0.len() < u16::MAX as usize {
-            tx.extend_from_slice(self.0.as_bytes());
-            tx.extend_from_slice(&self.0.len().to_le_bytes()[0..2]);
-        } else {
-            tx.extend_from_slice(&self.0.as_bytes()[0..u16::MAX as usize]);
-            tx.extend_from_slice(&[255, 255]);
-        }
+impl<'a> Convert<'a> for ShortString {
+    const MAX_SIZE: Option<usize> = Some(2 + u16::MAX as usize);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        let len = fit_str(tx, &self.0, u16::MAX as usize)?;
+        tx.prefixed(
+            |tx| tx.write(&(len as u16).to_le_bytes()),
+            |tx| tx.write(&self.0.as_bytes()[..len]),
+        )
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        let mut tx = vec![];
-        &self.to_bytes(&mut tx);
-        tx
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        let size = u16::decode(rx)?;
+        Ok(Self(decode_string(rx, size as usize)?))
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        let size = u16::from_bytes(rx)?;
-        Ok(Self(
-            String::from_utf8_lossy(&rx.split_off(rx.len() - size as usize)).to_string(),
-        ))
+    fn decode_into(&mut self, rx: &mut Decoder<'a>) -> Result<()> {
+        let size = u16::decode(rx)?;
+        decode_string_into(rx, &mut self.0, size as usize)
+    }
+    fn encoded_len(&self) -> usize {
+        2 + self.0.len()
     }
-
-    impl_net!();
 }
 
-impl Convert for MediumString {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        if self.0.len() < u32::MAX as usize {
-            tx.extend_from_slice(self.0.as_bytes());
-            tx.extend_from_slice(&self.0.len().to_le_bytes()[0..4]);
-        } else {
-            tx.extend_from_slice(&self.0.as_bytes()[0..u32::MAX as usize]);
-            tx.extend_from_slice(&[255, 255, 255, 255]);
-        }
+impl<'a> Convert<'a> for MediumString {
+    const MAX_SIZE: Option<usize> = size::sum(&[Some(4), Some(u32::MAX as usize)]);
+
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        let len = fit_str(tx, &self.0, u32::MAX as usize)?;
+        tx.prefixed(
+            |tx| tx.write(&(len as u32).to_le_bytes()),
+            |tx| tx.write(&self.0.as_bytes()[..len]),
+        )
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        let mut tx = vec![];
-        &self.to_bytes(&mut tx);
-        tx
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        let size = u32::decode(rx)?;
+        Ok(Self(decode_string(rx, size as usize)?))
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        let size = u32::from_bytes(rx)?;
-        Ok(Self(
-            String::from_utf8_lossy(&rx.split_off(rx.len() - size as usize)).to_string(),
-        ))
+    fn decode_into(&mut self, rx: &mut Decoder<'a>) -> Result<()> {
+        let size = u32::decode(rx)?;
+        decode_string_into(rx, &mut self.0, size as usize)
     }
+    fn encoded_len(&self) -> usize {
+        4 + self.0.len()
+    }
+}
 
-    impl_net!();
+impl<'a> Convert<'a> for LongString {
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        let len = fit_str(tx, &self.0, u64::MAX as usize)?;
+        tx.prefixed(
+            |tx| tx.write(&(len as u64).to_le_bytes()),
+            |tx| tx.write(&self.0.as_bytes()[..len]),
+        )
+    }
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        let size =
+            usize::try_from(u64::decode(rx)?).map_err(|_| Error::new(ErrorKind::LengthOverflow))?;
+        Ok(Self(decode_string(rx, size)?))
+    }
+    fn decode_into(&mut self, rx: &mut Decoder<'a>) -> Result<()> {
+        let size =
+            usize::try_from(u64::decode(rx)?).map_err(|_| Error::new(ErrorKind::LengthOverflow))?;
+        decode_string_into(rx, &mut self.0, size)
+    }
+    fn encoded_len(&self) -> usize {
+        8 + self.0.len()
+    }
 }
 
-impl Convert for LongString {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        if self.0.len() < u64::MAX as usize {
-            tx.extend_from_slice(self.0.as_bytes());
-            tx.extend_from_slice(&self.0.len().to_le_bytes()[0..8]);
-        } else {
-            tx.extend_from_slice(&self.0.as_bytes()[0..u64::MAX as usize]);
-            tx.extend_from_slice(&[255, 255, 255, 255, 255, 255, 255, 255]);
-        }
+impl<'a> Convert<'a> for String {
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        self.as_str().encode(tx)
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        let mut tx = vec![];
-        &self.to_bytes(&mut tx);
-        tx
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        let size = rx.read_var_int()?;
+        decode_string(rx, size as usize)
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        let size = u64::from_bytes(rx)?;
-        Ok(Self(
-            String::from_utf8_lossy(&rx.split_off(rx.len() - size as usize)).to_string(),
-        ))
+    fn decode_into(&mut self, rx: &mut Decoder<'a>) -> Result<()> {
+        let size = rx.read_var_int()?;
+        decode_string_into(rx, self, size as usize)
     }
+    fn encoded_len(&self) -> usize {
+        self.as_str().encoded_len()
+    }
+}
 
-    impl_net!();
+///
+/// Borrowed string, encoded the same way as `String`.
+///
+/// Decoding points into the input buffer, so the bytes must be valid UTF-8.
+///
+impl<'a: 'b, 'b> Convert<'a> for &'b str {
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        let len = fit_str(tx, self, u32::MAX as usize)?;
+        tx.prefixed(
+            |tx| tx.write_var_int(len as u32),
+            |tx| tx.write(&self.as_bytes()[..len]),
+        )
+    }
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        let size = rx.read_var_int()? as usize;
+        rx.limit_str_len(size)?;
+        core::str::from_utf8(rx.read_bytes(size)?).map_err(|_| Error::new(ErrorKind::InvalidUtf8))
+    }
+    fn encoded_len(&self) -> usize {
+        var_int_len(self.len() as u32) + self.len()
+    }
 }
 
-impl Convert for String {
-    fn to_bytes(&self, tx: &mut Vec<u8>) {
-        if self.len() < u32::MAX as usize {
-            tx.extend_from_slice(self.as_bytes());
-            let t = to_var_int(self.len() as u32);
-            let mut t = t.0[..t.1 as usize].to_vec();
-            t.reverse();
-            tx.extend_from_slice(&t);
+///
+/// Encoded the same way as `String`.
+///
+/// Decoding borrows from the input buffer, unless the bytes are not valid UTF-8 and `Decoder::set_lossy_utf8` asked to replace them.
+///
+impl<'a: 'b, 'b> Convert<'a> for Cow<'b, str> {
+    fn encode<W: Writer>(&self, tx: &mut Encoder<W>) -> Result<()> {
+        self.as_ref().encode(tx)
+    }
+    fn decode(rx: &mut Decoder<'a>) -> Result<Self> {
+        let size = rx.read_var_int()? as usize;
+        rx.limit_str_len(size)?;
+        let bytes = rx.read_bytes(size)?;
+        if rx.lossy_utf8() {
+            Ok(String::from_utf8_lossy(bytes))
         } else {
-            tx.extend_from_slice(&self.as_bytes()[0..u32::MAX as usize]);
-            let t = to_var_int(u32::MAX);
-            let mut t = t.0[..t.1 as usize].to_vec();
-            t.reverse();
-            tx.extend_from_slice(&t);
+            core::str::from_utf8(bytes)
+                .map(Cow::Borrowed)
+                .map_err(|_| Error::new(ErrorKind::InvalidUtf8))
         }
     }
-    fn to_bytes_return(&self) -> Vec<u8> {
-        let mut tx = vec![];
-        &self.to_bytes(&mut tx);
-        tx
+    fn encoded_len(&self) -> usize {
+        self.as_ref().encoded_len()
     }
-    fn from_bytes(rx: &mut Vec<u8>) -> io::Result<Self> {
-        let size = from_var_int_rev(&rx)?;
-        rx.truncate(rx.len() - size.1);
+}
+
+///
+/// Decode an owned string of `size` bytes, checking the limits of the decoder first.
+///
+fn decode_string(rx: &mut Decoder, size: usize) -> Result<String> {
+    let mut res = String::new();
+    decode_string_into(rx, &mut res, size)?;
+    Ok(res)
+}
 
-        Ok(String::from_utf8_lossy(&rx.split_off(rx.len() - size.0 as usize)).to_string())
+///
+/// Decode a string of `size` bytes into `s`, reusing its buffer.
+///
+fn decode_string_into(rx: &mut Decoder, s: &mut String, size: usize) -> Result<()> {
+    rx.limit_str_len(size)?;
+    rx.allocate(size)?;
+    let bytes = rx.read_bytes(size)?;
+    s.clear();
+    if rx.lossy_utf8() {
+        s.push_str(&String::from_utf8_lossy(bytes));
+    } else {
+        s.push_str(core::str::from_utf8(bytes).map_err(|_| Error::new(ErrorKind::InvalidUtf8))?);
     }
+    Ok(())
+}
 
-    impl_net!();
+///
+/// Length of `s` to write, a truncated string is cut at a char boundary.
+///
+fn fit_str<W: Writer>(tx: &Encoder<W>, s: &str, max: usize) -> Result<usize> {
+    let mut len = tx.fit_len(s.len(), max)?;
+    while !s.is_char_boundary(len) {
+        len -= 1;
+    }
+    Ok(len)
 }
@@ -1,50 +1,45 @@
-use std::io;
-use std::io::{Error, ErrorKind};
+use crate::types::decoder::Decoder;
+use crate::types::error::{Error, ErrorKind, Result};
 
-pub fn to_var_int(i: u32) -> ([u8; 16], u8) {
-    varint_simd::encode(i)
+///
+/// LEB128 bytes of `i` and how many of them are used.
+///
+pub fn to_var_int(mut i: u32) -> ([u8; 5], u8) {
+    let mut bytes = [0u8; 5];
+    let mut len = 0;
+    while i >= 0x80 {
+        bytes[len] = (i as u8) | 0x80;
+        i >>= 7;
+        len += 1;
+    }
+    bytes[len] = i as u8;
+    (bytes, len as u8 + 1)
 }
 
-pub fn from_var_int(data: &[u8]) -> io::Result<(u32, usize)> {
-    let mut value = 0u32;
-    if data.len() < 5 {
-        for i in 0..data.len() {
-            value |= ((data[i] & 0x7F) as u32) << 7 * i;
-
-            if (data[i] & 0x80) == 0 {
-                return Ok((value, i + 1));
-            }
-        }
-    } else {
-        for i in 0..5 {
-            value |= ((data[i] & 0x7F) as u32) << 7 * i;
-
-            if (data[i] & 0x80) == 0 {
-                return Ok((value, i + 1));
-            }
-        }
-    }
-    Err(Error::from(ErrorKind::InvalidData))
+///
+/// Number of bytes `to_var_int` writes for `i`.
+///
+pub fn var_int_len(i: u32) -> usize {
+    (38 - (i | 1).leading_zeros() as usize) / 7
 }
 
-pub fn from_var_int_rev(data: &Vec<u8>) -> io::Result<(u32, usize)> {
+pub fn from_var_int(data: &[u8]) -> Result<(u32, usize)> {
     let mut value = 0u32;
-    if data.len() < 5 {
-        for i in 0..data.len() {
-            value |= ((data[data.len() - 1 - i] & 0x7F) as u32) << 7 * i;
-
-            if (data[i] & 0x80) == 0 {
-                return Ok((value, i + 1));
-            }
+    for (i, u) in data.iter().take(5).enumerate() {
+        if i == 4 && *u > 0x0F {
+            break;
         }
-    } else {
-        for i in 0..5 {
-            value |= ((data[data.len() - 1 - i] & 0x7F) as u32) << 7 * i;
+        value |= ((u & 0x7F) as u32) << (7 * i);
 
-            if (data[data.len() - 1 - i] & 0x80) == 0 {
-                return Ok((value, i + 1));
-            }
+        if (u & 0x80) == 0 {
+            return Ok((value, i + 1));
         }
     }
-    Err(Error::from(ErrorKind::InvalidData))
-}
\ No newline at end of file
+    Err(Error::new(ErrorKind::InvalidVarInt))
+}
+
+pub fn from_var_int_rev(data: &[u8]) -> Result<(u32, usize)> {
+    let mut rx = Decoder::new(data);
+    let value = rx.read_var_int()?;
+    Ok((value, data.len() - rx.remaining()))
+}
@@ -0,0 +1,178 @@
+use std::collections::{HashMap, HashSet};
+
+use naumi::nmacro::NaumiConvert;
+use naumi::types::decoder::Decoder;
+use naumi::types::iters::TinyVec;
+use naumi::types::layout::Layout;
+use naumi::types::Convert;
+
+const LAYOUTS: [Layout; 2] = [Layout::Reverse, Layout::Forward];
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+struct Frame {
+    id: u32,
+    payload: Vec<u8>,
+    tags: Vec<String>,
+}
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+#[naumi(forward)]
+struct ForwardFrame {
+    id: u32,
+    payload: Vec<u8>,
+    tags: Vec<String>,
+}
+
+///
+/// Decode `value` encoded with `layout` into `into`.
+///
+fn decode_into<T: for<'a> Convert<'a>>(value: &T, into: &mut T, layout: Layout) {
+    let bytes = value.try_to_bytes_with(layout).unwrap();
+    let mut rx = Decoder::with_layout(&bytes, layout);
+    into.decode_into(&mut rx).unwrap();
+    assert!(rx.is_empty());
+}
+
+#[test]
+fn nested_vectors() {
+    for layout in LAYOUTS {
+        let mut into: Vec<Vec<u16>> = vec![Vec::with_capacity(8), Vec::with_capacity(8)];
+        into.reserve(4);
+        let outer = (into.as_ptr(), into.capacity());
+        let inner: Vec<_> = into.iter().map(|v| (v.as_ptr(), v.capacity())).collect();
+
+        let value = vec![vec![1, 2, 3], vec![4]];
+        decode_into(&value, &mut into, layout);
+        assert_eq!(into, value);
+        assert_eq!((into.as_ptr(), into.capacity()), outer);
+        for (v, (ptr, cap)) in into.iter().zip(&inner) {
+            assert_eq!((v.as_ptr(), v.capacity()), (*ptr, *cap));
+        }
+
+        // Fewer elements keep the outer allocation, more elements are appended.
+        decode_into(&vec![vec![5]], &mut into, layout);
+        assert_eq!(into, [[5]]);
+        assert_eq!((into.as_ptr(), into.capacity()), outer);
+        let value = vec![vec![1], vec![2, 2], vec![3, 3, 3]];
+        decode_into(&value, &mut into, layout);
+        assert_eq!(into, value);
+        assert_eq!(into[0].as_ptr(), inner[0].0);
+    }
+}
+
+#[test]
+fn strings_and_options() {
+    for layout in LAYOUTS {
+        let mut into = String::with_capacity(32);
+        let before = (into.as_ptr(), into.capacity());
+        decode_into(&"hello".to_string(), &mut into, layout);
+        assert_eq!(into, "hello");
+        assert_eq!((into.as_ptr(), into.capacity()), before);
+
+        let mut into = Some(String::with_capacity(32));
+        let before = into.as_ref().map(|s| (s.as_ptr(), s.capacity()));
+        decode_into(&Some("hi".to_string()), &mut into, layout);
+        assert_eq!(into.as_deref(), Some("hi"));
+        assert_eq!(into.as_ref().map(|s| (s.as_ptr(), s.capacity())), before);
+
+        decode_into(&None, &mut into, layout);
+        assert_eq!(into, None);
+        decode_into(&Some("new".to_string()), &mut into, layout);
+        assert_eq!(into.as_deref(), Some("new"));
+    }
+}
+
+#[test]
+fn hash_collections() {
+    for layout in LAYOUTS {
+        let mut into: HashMap<u8, String> = HashMap::with_capacity(64);
+        into.insert(9, "stale".into());
+        let capacity = into.capacity();
+        let value = HashMap::from([(1, "a".to_string()), (2, "b".to_string())]);
+        decode_into(&value, &mut into, layout);
+        assert_eq!(into, value);
+        assert_eq!(into.capacity(), capacity);
+
+        let mut into: HashSet<u32> = HashSet::with_capacity(64);
+        into.insert(9);
+        let capacity = into.capacity();
+        let value = HashSet::from([1, 2, 3]);
+        decode_into(&value, &mut into, layout);
+        assert_eq!(into, value);
+        assert_eq!(into.capacity(), capacity);
+    }
+}
+
+#[test]
+fn tiny_vectors() {
+    for layout in LAYOUTS {
+        let mut into = TinyVec(Vec::<String>::with_capacity(16));
+        into.0.push(String::with_capacity(16));
+        let outer = (into.0.as_ptr(), into.0.capacity());
+        let inner = into.0[0].as_ptr();
+
+        decode_into(
+            &TinyVec(vec!["a".to_string(), "b".into()]),
+            &mut into,
+            layout,
+        );
+        assert_eq!(into.0, ["a", "b"]);
+        assert_eq!((into.0.as_ptr(), into.0.capacity()), outer);
+        assert_eq!(into.0[0].as_ptr(), inner);
+    }
+}
+
+#[test]
+fn update_from_bytes_in_both_layouts() {
+    let mut frame = Frame {
+        id: 0,
+        payload: Vec::with_capacity(16),
+        tags: vec![String::with_capacity(16)],
+    };
+    let before = (frame.payload.as_ptr(), frame.tags[0].as_ptr());
+    let first = Frame {
+        id: 1,
+        payload: vec![1, 2],
+        tags: vec!["a".into()],
+    };
+    let second = Frame {
+        id: 2,
+        payload: vec![3],
+        tags: vec![],
+    };
+    // Frames are taken from the end of the buffer in `Layout::Reverse`.
+    let mut buf = second.to_bytes_return();
+    first.to_bytes(&mut buf);
+    frame.update_from_bytes(&mut buf).unwrap();
+    assert_eq!(frame, first);
+    assert_eq!((frame.payload.as_ptr(), frame.tags[0].as_ptr()), before);
+    frame.update_from_bytes(&mut buf).unwrap();
+    assert_eq!(frame, second);
+    assert!(buf.is_empty());
+
+    let mut frame = ForwardFrame {
+        id: 0,
+        payload: Vec::with_capacity(16),
+        tags: vec![String::with_capacity(16)],
+    };
+    let before = (frame.payload.as_ptr(), frame.tags[0].as_ptr());
+    let first = ForwardFrame {
+        id: 1,
+        payload: vec![1, 2],
+        tags: vec!["a".into()],
+    };
+    let second = ForwardFrame {
+        id: 2,
+        payload: vec![3],
+        tags: vec![],
+    };
+    // And from its start in `Layout::Forward`.
+    let mut buf = first.to_bytes_return();
+    second.to_bytes(&mut buf);
+    frame.update_from_bytes(&mut buf).unwrap();
+    assert_eq!(frame, first);
+    assert_eq!((frame.payload.as_ptr(), frame.tags[0].as_ptr()), before);
+    frame.update_from_bytes(&mut buf).unwrap();
+    assert_eq!(frame, second);
+    assert!(buf.is_empty());
+}
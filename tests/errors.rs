@@ -0,0 +1,182 @@
+use std::io;
+
+use naumi::nmacro::NaumiConvert;
+use naumi::types::error::{Error, ErrorKind, Segment};
+use naumi::types::layout::Layout;
+use naumi::types::num::varint::VarInt;
+use naumi::types::{decode_from_slice_with, Convert};
+
+const LAYOUTS: [Layout; 2] = [Layout::Reverse, Layout::Forward];
+
+#[derive(NaumiConvert)]
+struct Item {
+    name: String,
+    price: VarInt,
+    qty: u16,
+}
+
+#[derive(NaumiConvert)]
+struct Order {
+    id: u32,
+    items: Vec<Item>,
+    note: Option<String>,
+    paid: bool,
+}
+
+#[derive(NaumiConvert)]
+enum Status {
+    Pending,
+    Paid { at: u64 },
+    Refunded(String, Vec<u8>),
+    Note(String),
+}
+
+#[derive(NaumiConvert)]
+struct Invoice {
+    id: u32,
+    status: Status,
+}
+
+fn order() -> Order {
+    let items = ["tea", "cake", "jam", "zest", "pie"]
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| Item {
+            name: name.into(),
+            price: VarInt(100 * i as u32 + 300),
+            qty: 1,
+        })
+        .collect();
+    Order {
+        id: 7,
+        items,
+        note: None,
+        paid: true,
+    }
+}
+
+///
+/// The first `len` bytes the decoder reads, it starts from the end in `Layout::Reverse`.
+///
+fn cut(bytes: &[u8], len: usize, layout: Layout) -> &[u8] {
+    match layout {
+        Layout::Reverse => &bytes[bytes.len() - len..],
+        Layout::Forward => &bytes[..len],
+    }
+}
+
+///
+/// Replace the byte `z` of an encoded string with invalid UTF-8.
+///
+fn corrupt(mut bytes: Vec<u8>) -> Vec<u8> {
+    let at = bytes.iter().position(|&b| b == b'z').unwrap();
+    bytes[at] = 0xFF;
+    bytes
+}
+
+fn decode_error<'a, T: Convert<'a>>(bytes: &'a [u8], layout: Layout) -> Error {
+    decode_from_slice_with::<T>(bytes, layout).err().unwrap()
+}
+
+#[test]
+fn path_to_a_collection_element() {
+    for layout in LAYOUTS {
+        let order = order();
+        let bytes = order.try_to_bytes_with(layout).unwrap();
+
+        // Everything up to the price of the fourth item.
+        let len = order.id.encoded_len()
+            + 1
+            + order.items[..3]
+                .iter()
+                .map(Item::encoded_len)
+                .sum::<usize>()
+            + order.items[3].name.encoded_len();
+        let e = decode_error::<Order>(cut(&bytes, len, layout), layout);
+        assert!(matches!(e.kind(), ErrorKind::UnexpectedEnd));
+        assert_eq!(e.type_name(), Some("Order"));
+        assert_eq!(
+            e.path().cloned().collect::<Vec<_>>(),
+            [
+                Segment::Field("items"),
+                Segment::Index(3),
+                Segment::Field("price")
+            ]
+        );
+        assert_eq!(
+            e.to_string(),
+            "Order.items[3].price: unexpected end of input"
+        );
+
+        let bytes = corrupt(bytes);
+        assert_eq!(
+            decode_error::<Order>(&bytes, layout).to_string(),
+            "Order.items[3].name: invalid UTF-8"
+        );
+    }
+}
+
+#[test]
+fn path_through_a_variant() {
+    for layout in LAYOUTS {
+        let bytes = Status::Refunded("zero".into(), vec![1])
+            .try_to_bytes_with(layout)
+            .unwrap();
+        assert_eq!(
+            decode_error::<Status>(&corrupt(bytes), layout).to_string(),
+            "Status::Refunded.0: invalid UTF-8"
+        );
+
+        // A single unnamed field is the variant itself.
+        let bytes = Status::Note("zero".into())
+            .try_to_bytes_with(layout)
+            .unwrap();
+        assert_eq!(
+            decode_error::<Status>(&corrupt(bytes), layout).to_string(),
+            "Status::Note: invalid UTF-8"
+        );
+
+        // The outermost type names the path.
+        let invoice = Invoice {
+            id: 1,
+            status: Status::Paid { at: 5 },
+        };
+        let bytes = invoice.try_to_bytes_with(layout).unwrap();
+        // The id, the tag and seven of the eight bytes of `at`.
+        let len = 4 + 1 + 7;
+        assert_eq!(
+            decode_error::<Invoice>(cut(&bytes, len, layout), layout).to_string(),
+            "Invoice.status::Paid.at: unexpected end of input"
+        );
+    }
+}
+
+#[test]
+fn conversion_to_io_error() {
+    let bytes = order().to_bytes_return();
+    let e = decode_error::<Order>(&bytes[1..], Layout::Reverse);
+    let message = e.to_string();
+    let e = io::Error::from(e);
+    assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+    assert_eq!(e.to_string(), message);
+    let inner = e.get_ref().and_then(|e| e.downcast_ref::<Error>()).unwrap();
+    assert!(matches!(inner.kind(), ErrorKind::UnexpectedEnd));
+    assert_eq!(inner.type_name(), Some("Order"));
+
+    let bytes = corrupt(order().to_bytes_return());
+    let e = io::Error::from(decode_error::<Order>(&bytes, Layout::Reverse));
+    assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(e.to_string(), "Order.items[3].name: invalid UTF-8");
+
+    // An I/O error keeps its kind, and comes back unchanged when there is no path.
+    let e = Error::from(io::Error::new(io::ErrorKind::BrokenPipe, "closed"));
+    let e = io::Error::from(e);
+    assert_eq!(e.kind(), io::ErrorKind::BrokenPipe);
+    assert_eq!(e.to_string(), "closed");
+    let e = Error::from(io::Error::new(io::ErrorKind::BrokenPipe, "closed")).field("items");
+    let e = io::Error::from(e);
+    assert_eq!(e.kind(), io::ErrorKind::BrokenPipe);
+    let inner = e.get_ref().and_then(|e| e.downcast_ref::<Error>()).unwrap();
+    let source = std::error::Error::source(inner).unwrap();
+    assert_eq!(source.to_string(), "closed");
+}
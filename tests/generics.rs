@@ -0,0 +1,100 @@
+use std::marker::PhantomData;
+
+use naumi::nmacro::NaumiConvert;
+use naumi::types::layout::Layout;
+use naumi::types::{decode_from_slice_with, Convert};
+
+const LAYOUTS: [Layout; 2] = [Layout::Reverse, Layout::Forward];
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+struct Tagged<'a, T> {
+    name: &'a str,
+    value: T,
+    raw: &'a [u8],
+}
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+enum Slot<'a, T> {
+    Empty,
+    Borrowed(&'a str),
+    Owned(T),
+}
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+struct Block<const N: usize> {
+    id: u8,
+    data: [u8; N],
+}
+
+///
+/// Not convertible, only used as a marker.
+///
+#[derive(Debug, PartialEq)]
+struct Meters;
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+#[naumi(bound = "")]
+struct Length<T> {
+    value: u32,
+    unit: PhantomData<T>,
+}
+
+#[test]
+fn lifetime_and_type_parameter() {
+    for layout in LAYOUTS {
+        let tagged = Tagged {
+            name: "id",
+            value: vec![1u16, 2],
+            raw: &[9, 8],
+        };
+        let bytes = tagged.try_to_bytes_with(layout).unwrap();
+        assert_eq!(bytes.len(), tagged.encoded_len());
+        assert_eq!(
+            decode_from_slice_with::<Tagged<Vec<u16>>>(&bytes, layout).unwrap(),
+            tagged
+        );
+
+        for slot in [Slot::Empty, Slot::Borrowed("x"), Slot::Owned(5u64)] {
+            let bytes = slot.try_to_bytes_with(layout).unwrap();
+            assert_eq!(
+                decode_from_slice_with::<Slot<u64>>(&bytes, layout).unwrap(),
+                slot
+            );
+        }
+    }
+}
+
+#[test]
+fn const_generic_array() {
+    for layout in LAYOUTS {
+        let block = Block {
+            id: 1,
+            data: [1, 2, 3, 4],
+        };
+        let bytes = block.try_to_bytes_with(layout).unwrap();
+        assert_eq!(bytes.len(), 5);
+        assert_eq!(
+            decode_from_slice_with::<Block<4>>(&bytes, layout).unwrap(),
+            block
+        );
+    }
+    assert_eq!(Block::<4>::MAX_SIZE, Some(5));
+    assert_eq!(Block::<32>::MAX_SIZE, Some(33));
+}
+
+#[test]
+fn empty_bound_for_a_marker_parameter() {
+    for layout in LAYOUTS {
+        let length = Length::<Meters> {
+            value: 12,
+            unit: PhantomData,
+        };
+        let bytes = length.try_to_bytes_with(layout).unwrap();
+        assert_eq!(bytes, 12u32.try_to_bytes_with(layout).unwrap());
+        assert_eq!(
+            decode_from_slice_with::<Length<Meters>>(&bytes, layout).unwrap(),
+            length
+        );
+    }
+    assert_eq!(Length::<Meters>::MAX_SIZE, Some(4));
+}
@@ -0,0 +1,366 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use naumi::nmacro::NaumiConvert;
+use naumi::types::iters::{LongVec, MediumVec, ShortVec, TinyVec};
+use naumi::types::layout::Layout;
+use naumi::types::num::varint::VarInt;
+use naumi::types::string::{LongString, MediumString, ShortString, TinyString};
+use naumi::types::{decode_from_slice_with, Convert};
+
+const LAYOUTS: [Layout; 2] = [Layout::Reverse, Layout::Forward];
+
+#[derive(NaumiConvert)]
+struct SomeStruct {
+    d1: u32,
+    d2: VarInt,
+    d3: String,
+}
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+struct Pair(u16, String);
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+struct Empty;
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+enum Shape {
+    Point,
+    Circle(u32),
+    Rect(u16, u16),
+    Label { text: String, size: Option<u8> },
+}
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+struct Wrapper<T> {
+    items: Vec<T>,
+    last: Option<T>,
+}
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+struct Nested {
+    shapes: Vec<Shape>,
+    pair: (Pair, Empty),
+    lookup: HashMap<String, Wrapper<u16>>,
+}
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+#[naumi(forward)]
+struct Header {
+    id: u64,
+    name: String,
+}
+
+///
+/// Encode in both layouts and decode back to the same value.
+///
+fn round_trip<T>(value: T)
+where
+    T: for<'a> Convert<'a> + PartialEq + Debug,
+{
+    for layout in LAYOUTS {
+        let bytes = value.try_to_bytes_with(layout).unwrap();
+        assert_eq!(bytes.len(), value.encoded_len());
+        assert_eq!(decode_from_slice_with::<T>(&bytes, layout).unwrap(), value);
+    }
+}
+
+///
+/// Same as `round_trip`, for types without `PartialEq`: the decoded value has to encode to the same bytes.
+///
+fn round_trip_bytes<T>(value: T)
+where
+    T: for<'a> Convert<'a>,
+{
+    for layout in LAYOUTS {
+        let bytes = value.try_to_bytes_with(layout).unwrap();
+        assert_eq!(bytes.len(), value.encoded_len());
+        let decoded = decode_from_slice_with::<T>(&bytes, layout).unwrap();
+        assert_eq!(decoded.try_to_bytes_with(layout).unwrap(), bytes);
+    }
+}
+
+#[test]
+fn reverse_bytes_match_the_original_format() {
+    // Peers running older versions read these bytes, so they must never change.
+    assert_eq!(200u8.to_bytes_return(), [200]);
+    assert_eq!(0x1234u16.to_bytes_return(), [52, 18]);
+    assert_eq!(0xDEADBEEFu32.to_bytes_return(), [239, 190, 173, 222]);
+    assert_eq!(
+        0x0102030405060708u64.to_bytes_return(),
+        [8, 7, 6, 5, 4, 3, 2, 1]
+    );
+    assert_eq!(
+        0x0102030405060708090A0B0C0D0E0F10u128.to_bytes_return(),
+        [16, 15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1]
+    );
+    assert_eq!((-5i8).to_bytes_return(), [251]);
+    assert_eq!((-300i16).to_bytes_return(), [212, 254]);
+    assert_eq!((-70000i32).to_bytes_return(), [144, 238, 254, 255]);
+    assert_eq!(
+        (-5_000_000_000i64).to_bytes_return(),
+        [0, 14, 250, 213, 254, 255, 255, 255]
+    );
+    assert_eq!(
+        (-1i128 << 100).to_bytes_return(),
+        [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 240, 255, 255, 255]
+    );
+    assert_eq!(123456usize.to_bytes_return(), [64, 226, 1, 0, 0, 0, 0, 0]);
+    assert_eq!(
+        (-123456isize).to_bytes_return(),
+        [192, 29, 254, 255, 255, 255, 255, 255]
+    );
+    assert_eq!(1.5f32.to_bytes_return(), [0, 0, 192, 63]);
+    assert_eq!((-2.25f64).to_bytes_return(), [0, 0, 0, 0, 0, 0, 2, 192]);
+    assert_eq!(true.to_bytes_return(), [1]);
+
+    assert_eq!(VarInt(0).to_bytes_return(), [0]);
+    assert_eq!(VarInt(300).to_bytes_return(), [2, 172]);
+    assert_eq!(VarInt(u32::MAX).to_bytes_return(), [15, 255, 255, 255, 255]);
+
+    assert_eq!(
+        "héllo".to_string().to_bytes_return(),
+        [104, 195, 169, 108, 108, 111, 6]
+    );
+    assert_eq!(TinyString("ab".into()).to_bytes_return(), [97, 98, 2]);
+    assert_eq!(ShortString("ab".into()).to_bytes_return(), [97, 98, 2, 0]);
+    assert_eq!(
+        MediumString("ab".into()).to_bytes_return(),
+        [97, 98, 2, 0, 0, 0]
+    );
+    assert_eq!(
+        LongString("ab".into()).to_bytes_return(),
+        [97, 98, 2, 0, 0, 0, 0, 0, 0, 0]
+    );
+
+    assert_eq!(vec![1u16, 2, 3].to_bytes_return(), [3, 0, 2, 0, 1, 0, 3]);
+    assert_eq!(TinyVec(vec![1u16, 2]).to_bytes_return(), [2, 0, 1, 0, 2]);
+    assert_eq!(
+        ShortVec(vec![1u16, 2]).to_bytes_return(),
+        [2, 0, 1, 0, 2, 0]
+    );
+    assert_eq!(
+        MediumVec(vec![1u16, 2]).to_bytes_return(),
+        [2, 0, 1, 0, 2, 0, 0, 0]
+    );
+    assert_eq!(
+        LongVec(vec![1u16, 2]).to_bytes_return(),
+        [2, 0, 1, 0, 2, 0, 0, 0, 0, 0, 0, 0]
+    );
+    assert_eq!(
+        vec!["a".to_string(), "bc".to_string()].to_bytes_return(),
+        [98, 99, 2, 97, 1, 2]
+    );
+    assert_eq!(HashSet::from([7u32]).to_bytes_return(), [7, 0, 0, 0, 1]);
+    assert_eq!(
+        HashMap::from([("k".to_string(), 9u16)]).to_bytes_return(),
+        [107, 1, 9, 0, 1]
+    );
+
+    assert_eq!(Some(5u16).to_bytes_return(), [5, 0, 1]);
+    assert_eq!(None::<u16>.to_bytes_return(), [0]);
+    assert_eq!(Some(vec![1u8, 2]).to_bytes_return(), [2, 1, 2, 1]);
+
+    assert_eq!((1u8,).to_bytes_return(), [1]);
+    assert_eq!((1u8, 2u16).to_bytes_return(), [2, 0, 1]);
+    assert_eq!(
+        (1u8, 2u16, "x".to_string()).to_bytes_return(),
+        [120, 1, 2, 0, 1]
+    );
+    assert_eq!((1u8, 2u8, 3u8, 4u8).to_bytes_return(), [4, 3, 2, 1]);
+    assert_eq!((1u8, 2u8, 3u8, 4u8, 5u8).to_bytes_return(), [5, 4, 3, 2, 1]);
+    assert_eq!(
+        (1u8, 2u8, 3u8, 4u8, 5u8, true).to_bytes_return(),
+        [1, 5, 4, 3, 2, 1]
+    );
+
+    // `examples/encode`.
+    let some_struct = SomeStruct {
+        d1: 929292929,
+        d2: VarInt(834),
+        d3: "Hello world!".to_string(),
+    };
+    assert_eq!(
+        some_struct.to_bytes_return(),
+        [72, 101, 108, 108, 111, 32, 119, 111, 114, 108, 100, 33, 12, 6, 194, 129, 226, 99, 55]
+    );
+    assert_eq!(Pair(7, "ab".into()).to_bytes_return(), [97, 98, 2, 7, 0]);
+    assert_eq!(Shape::Point.to_bytes_return(), [0]);
+    assert_eq!(Shape::Circle(9).to_bytes_return(), [9, 0, 0, 0, 1]);
+}
+
+#[test]
+fn built_in_types_round_trip() {
+    round_trip(200u8);
+    round_trip(0x1234u16);
+    round_trip(0xDEADBEEFu32);
+    round_trip(u64::MAX);
+    round_trip(u128::MAX);
+    round_trip(i8::MIN);
+    round_trip(-300i16);
+    round_trip(-70000i32);
+    round_trip(i64::MIN);
+    round_trip(i128::MIN);
+    round_trip(usize::MAX);
+    round_trip(isize::MIN);
+    round_trip(1.5f32);
+    round_trip(-2.25f64);
+    round_trip(true);
+    round_trip(false);
+    round_trip(());
+    round_trip(PhantomData::<String>);
+
+    for i in [0, 127, 128, 16_383, 16_384, u32::MAX] {
+        round_trip_bytes(VarInt(i));
+    }
+
+    round_trip(String::new());
+    round_trip("héllo".to_string());
+    round_trip_bytes(TinyString("ab".into()));
+    round_trip_bytes(ShortString("ab".into()));
+    round_trip_bytes(MediumString("ab".into()));
+    round_trip_bytes(LongString("ab".into()));
+
+    round_trip(Vec::<u16>::new());
+    round_trip(vec![1u16, 2, 3]);
+    round_trip(vec!["a".to_string(), "bc".to_string()]);
+    round_trip_bytes(TinyVec(vec![1u16, 2]));
+    round_trip_bytes(ShortVec(vec![1u16, 2]));
+    round_trip_bytes(MediumVec(vec![1u16, 2]));
+    round_trip_bytes(LongVec(vec![1u16, 2]));
+    round_trip([1u32, 2, 3, 4]);
+    round_trip(HashSet::from([1u32, 2, 3]));
+    round_trip(HashMap::from([
+        ("a".to_string(), vec![Some(1u8), None]),
+        ("b".to_string(), vec![]),
+    ]));
+
+    round_trip(Some(5u16));
+    round_trip(None::<u16>);
+    round_trip(Some(Some(vec![1u8, 2])));
+
+    round_trip((1u8,));
+    round_trip((1u8, 2u16));
+    round_trip((1u8, 2u16, "x".to_string()));
+    round_trip((1u8, 2u8, 3u8, 4u8));
+    round_trip((1u8, 2u8, 3u8, 4u8, 5u8));
+    round_trip((1u8, 2u8, 3u8, 4u8, 5u8, true));
+}
+
+#[test]
+fn borrowed_types_round_trip() {
+    for layout in LAYOUTS {
+        let bytes = "héllo".try_to_bytes_with(layout).unwrap();
+        assert_eq!(
+            decode_from_slice_with::<&str>(&bytes, layout).unwrap(),
+            "héllo"
+        );
+        let decoded = decode_from_slice_with::<Cow<str>>(&bytes, layout).unwrap();
+        assert!(matches!(decoded, Cow::Borrowed("héllo")));
+
+        let raw: &[u8] = &[1, 2, 3];
+        let bytes = raw.try_to_bytes_with(layout).unwrap();
+        assert_eq!(
+            decode_from_slice_with::<&[u8]>(&bytes, layout).unwrap(),
+            raw
+        );
+    }
+}
+
+#[test]
+fn borrowed_bytes_keep_their_order() {
+    let raw: &[u8] = &[1, 2, 3];
+    let owned = raw.to_vec();
+
+    // `Vec<u8>` writes its elements in reverse like any collection, `&[u8]` keeps them in order.
+    assert_eq!(
+        owned.try_to_bytes_with(Layout::Reverse).unwrap(),
+        [3, 2, 1, 3]
+    );
+    assert_eq!(
+        raw.try_to_bytes_with(Layout::Reverse).unwrap(),
+        [1, 2, 3, 3]
+    );
+    assert_eq!(
+        owned.try_to_bytes_with(Layout::Forward).unwrap(),
+        [3, 1, 2, 3]
+    );
+    assert_eq!(
+        raw.try_to_bytes_with(Layout::Forward).unwrap(),
+        [3, 1, 2, 3]
+    );
+
+    // So in `Layout::Reverse` the two are not interchangeable on the wire.
+    let bytes = owned.try_to_bytes_with(Layout::Reverse).unwrap();
+    assert_eq!(
+        decode_from_slice_with::<&[u8]>(&bytes, Layout::Reverse).unwrap(),
+        [3, 2, 1]
+    );
+}
+
+#[test]
+fn derived_types_round_trip() {
+    round_trip_bytes(SomeStruct {
+        d1: 929292929,
+        d2: VarInt(834),
+        d3: "Hello world!".to_string(),
+    });
+    round_trip(Pair(7, "ab".into()));
+    round_trip(Empty);
+    round_trip(Shape::Point);
+    round_trip(Shape::Circle(9));
+    round_trip(Shape::Rect(3, 4));
+    round_trip(Shape::Label {
+        text: "hi".into(),
+        size: Some(12),
+    });
+    round_trip(Wrapper {
+        items: vec![1u64, 2],
+        last: Some(3),
+    });
+    round_trip(Nested {
+        shapes: vec![
+            Shape::Rect(1, 2),
+            Shape::Label {
+                text: "x".into(),
+                size: None,
+            },
+        ],
+        pair: (Pair(1, "y".into()), Empty),
+        lookup: HashMap::from([(
+            "k".to_string(),
+            Wrapper {
+                items: vec![5],
+                last: None,
+            },
+        )]),
+    });
+    round_trip(Header {
+        id: 9,
+        name: "n".into(),
+    });
+}
+
+#[test]
+fn forward_from_bytes_drains_only_the_value() {
+    let header = Header {
+        id: 9,
+        name: "name".into(),
+    };
+    let mut buf = header.to_bytes_return();
+    assert_eq!(buf.len(), header.encoded_len());
+    // Length-first: the id comes first, in the order of the fields.
+    assert_eq!(buf[..8], 9u64.to_le_bytes());
+    buf.extend_from_slice(&[1, 2, 3]);
+
+    assert_eq!(Header::from_bytes(&mut buf).unwrap(), header);
+    assert_eq!(buf, [1, 2, 3]);
+
+    // The default layout takes the value from the end instead.
+    let mut buf = vec![1, 2, 3];
+    buf.extend_from_slice(&Pair(7, "ab".into()).to_bytes_return());
+    assert_eq!(Pair::from_bytes(&mut buf).unwrap(), Pair(7, "ab".into()));
+    assert_eq!(buf, [1, 2, 3]);
+}
@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use naumi::nmacro::NaumiConvert;
+use naumi::types::decoder::Decoder;
+use naumi::types::encoder::Encoder;
+use naumi::types::error::ErrorKind;
+use naumi::types::iters::LongVec;
+use naumi::types::layout::Layout;
+use naumi::types::limits::{DecodeLimits, Limit};
+use naumi::types::string::TinyString;
+use naumi::types::{decode_from_slice_limited, decode_from_slice_with, Convert};
+
+const LAYOUTS: [Layout; 2] = [Layout::Reverse, Layout::Forward];
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+struct Ping;
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+enum Tree {
+    Leaf(u8),
+    Node(Vec<Tree>),
+}
+
+fn tree(depth: usize) -> Tree {
+    (0..depth).fold(Tree::Leaf(1), |tree, _| Tree::Node(vec![tree]))
+}
+
+fn limited(f: impl FnOnce(&mut DecodeLimits)) -> DecodeLimits {
+    let mut limits = DecodeLimits::UNLIMITED;
+    f(&mut limits);
+    limits
+}
+
+///
+/// Decode `value` with `limits` in both layouts, returning the exceeded limit.
+///
+fn exceeded<T: for<'a> Convert<'a>>(value: &T, limits: DecodeLimits) -> Vec<Limit> {
+    LAYOUTS
+        .into_iter()
+        .map(|layout| {
+            let bytes = value.try_to_bytes_with(layout).unwrap();
+            match T::decode(&mut Decoder::with_limits(&bytes, layout, limits)) {
+                Ok(_) => panic!("decoded past the limits"),
+                Err(e) => match e.kind() {
+                    ErrorKind::LimitExceeded(limit) => *limit,
+                    kind => panic!("unexpected error {kind:?}"),
+                },
+            }
+        })
+        .collect()
+}
+
+///
+/// Only a VarInt length prefix, without any elements after it.
+///
+fn prefix(len: u32, layout: Layout) -> Vec<u8> {
+    let mut tx = Encoder::with_layout(Vec::new(), layout);
+    tx.write_var_int(len).unwrap();
+    tx.into_inner()
+}
+
+#[test]
+fn counts_above_the_remaining_bytes_fail_early() {
+    for layout in LAYOUTS {
+        let bytes = prefix(u32::MAX, layout);
+        macro_rules! unexpected_end {
+            ($($ty:ty),* $(,)?) => {
+                $(assert!(matches!(
+                    decode_from_slice_with::<$ty>(&bytes, layout).unwrap_err().kind(),
+                    ErrorKind::UnexpectedEnd
+                ));)*
+            };
+        }
+        unexpected_end!(Vec<u8>, Vec<String>, Vec<Option<()>>, HashMap<u8, ()>);
+    }
+}
+
+#[test]
+fn zero_sized_elements_are_capped() {
+    for layout in LAYOUTS {
+        let bytes = prefix(u32::MAX, layout);
+        macro_rules! too_long {
+            ($($ty:ty),* $(,)?) => {
+                $(assert!(matches!(
+                    decode_from_slice_with::<$ty>(&bytes, layout).unwrap_err().kind(),
+                    ErrorKind::LimitExceeded(Limit::Len)
+                ));)*
+            };
+        }
+        too_long!(Vec<()>, Vec<PhantomData<u64>>, Vec<Ping>, Vec<[(); 4]>);
+
+        // Not even unlimited decoding goes past the cap.
+        let mut rx = Decoder::with_limits(&bytes, layout, DecodeLimits::UNLIMITED);
+        assert!(matches!(
+            Vec::<()>::decode(&mut rx).unwrap_err().kind(),
+            ErrorKind::LimitExceeded(Limit::Len)
+        ));
+
+        let len = DecodeLimits::MAX_ZERO_SIZED_LEN;
+        let bytes = prefix(len as u32, layout);
+        let pings = decode_from_slice_with::<Vec<Ping>>(&bytes, layout).unwrap();
+        assert_eq!(pings.len(), len);
+    }
+
+    let bytes = [0xFF; 8];
+    assert!(matches!(
+        decode_from_slice_with::<LongVec<()>>(&bytes, Layout::Reverse)
+            .err()
+            .unwrap()
+            .kind(),
+        ErrorKind::LimitExceeded(Limit::Len)
+    ));
+}
+
+#[test]
+fn collection_length() {
+    let limits = limited(|l| l.max_len = 3);
+    assert_eq!(exceeded(&vec![1u8, 2, 3, 4], limits), [Limit::Len; 2]);
+    assert_eq!(
+        exceeded(&HashMap::from([(1u8, 1u8), (2, 2), (3, 3), (4, 4)]), limits),
+        [Limit::Len; 2]
+    );
+    assert_eq!(exceeded(&LongVec(vec![1u16; 4]), limits), [Limit::Len; 2]);
+    let wide = Tree::Node(vec![
+        Tree::Leaf(1),
+        Tree::Leaf(2),
+        Tree::Leaf(3),
+        Tree::Leaf(4),
+    ]);
+    assert_eq!(exceeded(&wide, limits), [Limit::Len; 2]);
+
+    let bytes = vec![1u8, 2, 3].to_bytes_return();
+    assert_eq!(
+        decode_from_slice_limited::<Vec<u8>>(&bytes, limits).unwrap(),
+        [1, 2, 3]
+    );
+}
+
+#[test]
+fn string_length() {
+    let limits = limited(|l| l.max_str_len = 4);
+    assert_eq!(exceeded(&"hello".to_string(), limits), [Limit::StrLen; 2]);
+    assert_eq!(
+        exceeded(&TinyString("hello".into()), limits),
+        [Limit::StrLen; 2]
+    );
+    assert_eq!(
+        exceeded(&vec!["hi".to_string(), "hello".into()], limits),
+        [Limit::StrLen; 2]
+    );
+
+    // Only the strings count, not the collections around them.
+    let bytes = vec!["hi".to_string(); 10].to_bytes_return();
+    assert_eq!(
+        decode_from_slice_limited::<Vec<String>>(&bytes, limits)
+            .unwrap()
+            .len(),
+        10
+    );
+    let bytes = "hello".to_bytes_return();
+    assert!(matches!(
+        decode_from_slice_limited::<&str>(&bytes, limits)
+            .unwrap_err()
+            .kind(),
+        ErrorKind::LimitExceeded(Limit::StrLen)
+    ));
+}
+
+#[test]
+fn allocation() {
+    let map: HashMap<u32, String> = (0..100).map(|i| (i, i.to_string())).collect();
+    let entries = map.len() * std::mem::size_of::<(u32, String)>();
+    assert_eq!(
+        exceeded(&map, limited(|l| l.max_alloc = entries - 1)),
+        [Limit::Alloc; 2]
+    );
+
+    // The strings are allocated on top of the entries.
+    let strings: usize = map.values().map(String::len).sum();
+    assert_eq!(
+        exceeded(&map, limited(|l| l.max_alloc = entries + strings - 1)),
+        [Limit::Alloc; 2]
+    );
+    let bytes = map.to_bytes_return();
+    let limits = limited(|l| l.max_alloc = entries + strings);
+    assert_eq!(
+        decode_from_slice_limited::<HashMap<u32, String>>(&bytes, limits).unwrap(),
+        map
+    );
+}
+
+#[test]
+fn nesting_depth() {
+    // Every level is the enum and the vector inside it.
+    let limits = limited(|l| l.max_depth = 20);
+    assert_eq!(exceeded(&tree(10), limits), [Limit::Depth; 2]);
+    let bytes = tree(9).to_bytes_return();
+    assert_eq!(
+        decode_from_slice_limited::<Tree>(&bytes, limits).unwrap(),
+        tree(9)
+    );
+
+    // The default limit stops a deep tree, which would overflow the stack otherwise.
+    let bytes = tree(100).to_bytes_return();
+    assert!(matches!(
+        decode_from_slice_limited::<Tree>(&bytes, DecodeLimits::default())
+            .unwrap_err()
+            .kind(),
+        ErrorKind::LimitExceeded(Limit::Depth)
+    ));
+    assert!(matches!(
+        decode_from_slice_with::<Tree>(&bytes, Layout::Reverse)
+            .unwrap_err()
+            .kind(),
+        ErrorKind::LimitExceeded(Limit::Depth)
+    ));
+}
@@ -0,0 +1,227 @@
+//! Decoding arbitrary bytes has to fail with an error, never with a panic.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use naumi::nmacro::NaumiConvert;
+use naumi::types::iters::LongVec;
+use naumi::types::layout::Layout;
+use naumi::types::num::varint::VarInt;
+use naumi::types::string::LongString;
+use naumi::types::{decode_from_slice_with, Convert};
+
+#[derive(NaumiConvert)]
+struct Item {
+    name: String,
+    price: VarInt,
+    qty: u16,
+}
+
+#[derive(NaumiConvert)]
+struct Order {
+    id: u32,
+    items: Vec<Item>,
+    note: Option<String>,
+    paid: bool,
+}
+
+#[derive(NaumiConvert)]
+enum Status {
+    Pending,
+    Paid { at: u64 },
+    Refunded(String, Vec<u8>),
+}
+
+#[derive(NaumiConvert)]
+#[naumi(varint_tag)]
+enum Wide {
+    A(u8),
+    #[naumi(tag = 1000)]
+    B(Vec<Status>),
+}
+
+#[derive(NaumiConvert)]
+enum Message {
+    Ping(u8),
+    Text(String),
+    #[naumi(other)]
+    Raw(u8, Vec<u8>),
+}
+
+#[derive(NaumiConvert)]
+#[naumi(versioned)]
+struct Versioned {
+    id: u32,
+    #[naumi(since = 2)]
+    tags: Vec<String>,
+}
+
+#[derive(NaumiConvert)]
+struct Borrowed<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+    cow: Cow<'a, str>,
+}
+
+#[derive(NaumiConvert)]
+#[naumi(forward)]
+struct Forward {
+    id: u64,
+    name: String,
+}
+
+///
+/// Small xorshift generator, so the inputs are the same on every run.
+///
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    ///
+    /// Random bytes, biased towards values that matter to the format (tags, VarInt continuation, huge lengths).
+    ///
+    fn bytes(&mut self, max_len: usize) -> Vec<u8> {
+        const INTERESTING: [u8; 8] = [0, 1, 2, 3, 0x7F, 0x80, 0xFE, 0xFF];
+        let len = self.below(max_len + 1);
+        (0..len)
+            .map(|_| match self.below(3) {
+                0 => INTERESTING[self.below(INTERESTING.len())],
+                _ => self.next() as u8,
+            })
+            .collect()
+    }
+}
+
+fn decode_all(buf: &[u8], layout: Layout) {
+    macro_rules! decode {
+        ($($ty:ty),* $(,)?) => {
+            $(let _ = decode_from_slice_with::<$ty>(buf, layout);)*
+        };
+    }
+    decode!(
+        u8,
+        u64,
+        i128,
+        f64,
+        bool,
+        VarInt,
+        String,
+        LongString,
+        LongVec<u32>,
+        Vec<Option<u8>>,
+        [u16; 4],
+        (u8, String, bool),
+        HashMap<String, Vec<Option<u8>>>,
+        &str,
+        &[u8],
+        Cow<str>,
+        Item,
+        Order,
+        Status,
+        Wide,
+        Message,
+        Versioned,
+        Borrowed,
+        Forward,
+    );
+}
+
+fn from_bytes_all(buf: &[u8]) {
+    macro_rules! from_bytes {
+        ($($ty:ty),* $(,)?) => {
+            $(let _ = <$ty>::from_bytes(&mut buf.to_vec());)*
+        };
+    }
+    from_bytes!(
+        u32,
+        String,
+        LongVec<u32>,
+        HashMap<String, Vec<Option<u8>>>,
+        Order,
+        Status,
+        Message,
+        Versioned,
+        Forward,
+    );
+}
+
+///
+/// Valid encodings, which are then cut and corrupted.
+///
+fn samples(layout: Layout) -> Vec<Vec<u8>> {
+    let order = Order {
+        id: 7,
+        items: vec![
+            Item {
+                name: "tea".into(),
+                price: VarInt(300),
+                qty: 2,
+            },
+            Item {
+                name: "cake".into(),
+                price: VarInt(70_000),
+                qty: 1,
+            },
+        ],
+        note: Some("to go".into()),
+        paid: true,
+    };
+    let map = HashMap::from([("a".to_string(), vec![Some(1), None])]);
+    vec![
+        order.try_to_bytes_with(layout).unwrap(),
+        Status::Refunded("late".into(), vec![1, 2, 3])
+            .try_to_bytes_with(layout)
+            .unwrap(),
+        Wide::B(vec![Status::Paid { at: 5 }, Status::Pending])
+            .try_to_bytes_with(layout)
+            .unwrap(),
+        Message::Raw(9, vec![4, 5, 6])
+            .try_to_bytes_with(layout)
+            .unwrap(),
+        map.try_to_bytes_with(layout).unwrap(),
+        LongString("long".into()).try_to_bytes_with(layout).unwrap(),
+        LongVec(vec![1u32, 2, 3]).try_to_bytes_with(layout).unwrap(),
+    ]
+}
+
+#[test]
+fn random_bytes() {
+    let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+    for _ in 0..100_000 {
+        let buf = rng.bytes(48);
+        decode_all(&buf, Layout::Reverse);
+        decode_all(&buf, Layout::Forward);
+        from_bytes_all(&buf);
+    }
+}
+
+#[test]
+fn truncated_and_corrupted() {
+    let mut rng = Rng(0xD1B5_4A32_D192_ED03);
+    for layout in [Layout::Reverse, Layout::Forward] {
+        for sample in samples(layout) {
+            for at in 0..sample.len() {
+                decode_all(&sample[..at], layout);
+                decode_all(&sample[at..], layout);
+                from_bytes_all(&sample[..at]);
+            }
+            for _ in 0..500 {
+                let mut buf = sample.clone();
+                let at = rng.below(buf.len());
+                buf[at] = rng.next() as u8;
+                decode_all(&buf, layout);
+                from_bytes_all(&buf);
+            }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::io::Cursor;
+
+use naumi::types::error::{Error, ErrorKind};
+use naumi::types::net::{self, Oversized, ReceiveOptions};
+use naumi::types::string::TinyString;
+
+fn frames() -> Cursor<Vec<u8>> {
+    let mut tx = Vec::new();
+    net::send(&mut vec![7u8; 100], &mut tx).unwrap();
+    net::send(&mut "next".to_string(), &mut tx).unwrap();
+    Cursor::new(tx)
+}
+
+fn options(on_oversized: Oversized) -> ReceiveOptions {
+    ReceiveOptions {
+        max_frame_size: 16,
+        on_oversized,
+        ..ReceiveOptions::default()
+    }
+}
+
+fn assert_too_large(e: std::io::Error) {
+    let e = e.get_ref().and_then(|e| e.downcast_ref::<Error>()).unwrap();
+    assert!(matches!(
+        e.kind(),
+        ErrorKind::FrameTooLarge { size: 101, max: 16 }
+    ));
+}
+
+#[test]
+fn oversized_frame_is_skipped() {
+    let mut rx = frames();
+    let options = options(Oversized::Skip);
+    assert_too_large(net::receive_with::<Vec<u8>, _>(&mut rx, &options).unwrap_err());
+    assert_eq!(
+        net::receive_with::<String, _>(&mut rx, &options).unwrap(),
+        "next"
+    );
+}
+
+#[test]
+fn oversized_frame_is_left_unread() {
+    let mut rx = frames();
+    let options = options(Oversized::Close);
+    assert_too_large(net::receive_with::<Vec<u8>, _>(&mut rx, &options).unwrap_err());
+    // Only the length prefix was read, the connection is out of sync and has to be closed.
+    assert_eq!(rx.position(), 1);
+}
+
+#[test]
+fn invalid_utf8_is_replaced_when_lossy() {
+    let mut tx = Vec::new();
+    net::send(&mut "axb".to_string(), &mut tx).unwrap();
+    let at = tx.iter().position(|&b| b == b'x').unwrap();
+    tx[at] = 0xFF;
+
+    let e = net::receive::<String, _>(&mut Cursor::new(&tx)).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+    let options = ReceiveOptions {
+        lossy_utf8: true,
+        ..ReceiveOptions::default()
+    };
+    assert_eq!(
+        net::receive_with::<String, _>(&mut Cursor::new(&tx), &options).unwrap(),
+        "a\u{FFFD}b"
+    );
+}
+
+#[test]
+fn overflow_is_not_sent() {
+    let mut tx = Vec::new();
+    let e = net::send(&mut TinyString("a".repeat(256)), &mut tx).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+    let e = e.get_ref().and_then(|e| e.downcast_ref::<Error>()).unwrap();
+    assert!(matches!(e.kind(), ErrorKind::LengthOverflow));
+    // Nothing was written, so the stream is still in sync.
+    assert!(tx.is_empty());
+}
@@ -0,0 +1,106 @@
+use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt};
+
+use naumi::nmacro::NaumiConvert;
+use naumi::types::error::{Error, ErrorKind};
+use naumi::types::net::{self, AsyncConvert, Oversized, ReceiveOptions};
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+struct Chat {
+    id: u32,
+    text: String,
+    to: Vec<u32>,
+}
+
+fn chat() -> Chat {
+    Chat {
+        id: 7,
+        text: "hello".into(),
+        to: vec![1, 2, 3],
+    }
+}
+
+fn options(on_oversized: Oversized) -> ReceiveOptions {
+    ReceiveOptions {
+        max_frame_size: 16,
+        on_oversized,
+        ..ReceiveOptions::default()
+    }
+}
+
+fn assert_too_large(e: std::io::Error) {
+    let e = e.get_ref().and_then(|e| e.downcast_ref::<Error>()).unwrap();
+    assert!(matches!(
+        e.kind(),
+        ErrorKind::FrameTooLarge { size: 101, max: 16 }
+    ));
+}
+
+#[tokio::test]
+async fn round_trip() {
+    let (mut tx, mut rx) = duplex(64);
+    let writer = tokio::spawn(async move {
+        chat().async_send(&mut tx).await.unwrap();
+        net::async_send(&mut chat(), &mut tx).await.unwrap();
+        net::async_send(&mut "bye".to_string(), &mut tx)
+            .await
+            .unwrap();
+    });
+
+    assert_eq!(Chat::async_receive(&mut rx).await.unwrap(), chat());
+    assert_eq!(
+        net::async_receive::<Chat, _>(&mut rx).await.unwrap(),
+        chat()
+    );
+    let options = ReceiveOptions::default();
+    assert_eq!(
+        net::async_receive_with::<String, _>(&mut rx, &options)
+            .await
+            .unwrap(),
+        "bye"
+    );
+    writer.await.unwrap();
+
+    // The writer is gone, so the next frame never comes.
+    let e = Chat::async_receive(&mut rx).await.unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+#[tokio::test]
+async fn oversized_frame_is_skipped() {
+    let (mut tx, mut rx) = duplex(256);
+    net::async_send(&mut vec![7u8; 100], &mut tx).await.unwrap();
+    net::async_send(&mut "next".to_string(), &mut tx)
+        .await
+        .unwrap();
+    tx.shutdown().await.unwrap();
+
+    let options = options(Oversized::Skip);
+    assert_too_large(
+        net::async_receive_with::<Vec<u8>, _>(&mut rx, &options)
+            .await
+            .unwrap_err(),
+    );
+    assert_eq!(
+        net::async_receive_with::<String, _>(&mut rx, &options)
+            .await
+            .unwrap(),
+        "next"
+    );
+}
+
+#[tokio::test]
+async fn oversized_frame_is_left_unread() {
+    let (mut tx, mut rx) = duplex(256);
+    net::async_send(&mut vec![7u8; 100], &mut tx).await.unwrap();
+
+    let options = options(Oversized::Close);
+    assert_too_large(
+        net::async_receive_with::<Vec<u8>, _>(&mut rx, &options)
+            .await
+            .unwrap_err(),
+    );
+    // Only the length prefix was read, the connection is out of sync and has to be closed.
+    let mut payload = [0u8; 100];
+    rx.read_exact(&mut payload).await.unwrap();
+    assert_eq!(payload, [7; 100]);
+}
@@ -0,0 +1,49 @@
+//! Run with `cargo test --no-default-features --test no_std`, the std `Writer` impls cover these types otherwise.
+#![cfg(not(feature = "std"))]
+
+use naumi::nmacro::NaumiConvert;
+use naumi::types::error::ErrorKind;
+use naumi::types::{decode_from_slice, Convert};
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+struct Reading {
+    sensor: u8,
+    value: i32,
+    label: String,
+}
+
+fn reading() -> Reading {
+    Reading {
+        sensor: 3,
+        value: -40,
+        label: "temp".into(),
+    }
+}
+
+#[test]
+fn fixed_buffer() {
+    let mut buf = [0u8; 64];
+    let mut rest = &mut buf[..];
+    reading().to_writer(&mut rest).unwrap();
+    let len = 64 - rest.len();
+    assert_eq!(len, reading().encoded_len());
+    assert_eq!(buf[..len], reading().to_bytes_return());
+    assert_eq!(
+        decode_from_slice::<Reading>(&buf[..len]).unwrap(),
+        reading()
+    );
+
+    // Passing the slice itself works as well.
+    let mut buf = [0u8; 64];
+    reading().to_writer(&mut buf[..]).unwrap();
+    assert_eq!(buf[..len], reading().to_bytes_return());
+}
+
+#[test]
+fn full_buffer_is_an_error() {
+    let mut buf = [0u8; 64];
+    let len = reading().encoded_len();
+    let e = reading().to_writer(&mut buf[..len - 1]).unwrap_err();
+    assert!(matches!(e.kind(), ErrorKind::UnexpectedEnd));
+    assert!(reading().to_writer(&mut buf[..len]).is_ok());
+}
@@ -0,0 +1,160 @@
+use naumi::nmacro::NaumiConvert;
+use naumi::types::encoder::Encoder;
+use naumi::types::layout::Layout;
+use naumi::types::{decode_from_slice_with, Convert};
+
+const LAYOUTS: [Layout; 2] = [Layout::Reverse, Layout::Forward];
+
+///
+/// Oldest reader, knows two variants and skips the rest.
+///
+#[derive(NaumiConvert, Debug, PartialEq)]
+enum MessageV1 {
+    Ping(u8),
+    Text {
+        text: String,
+    },
+    #[naumi(other)]
+    Unknown,
+}
+
+///
+/// Keeps the variants it does not know, so they can be forwarded unchanged.
+///
+#[derive(NaumiConvert, Debug, PartialEq)]
+enum MessageV2 {
+    Ping(u8),
+    Text {
+        text: String,
+    },
+    Empty,
+    #[naumi(other)]
+    Raw(u8, Vec<u8>),
+}
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+enum MessageV3 {
+    Ping(u8),
+    Text {
+        text: String,
+    },
+    Empty,
+    Data(Vec<u8>, u16),
+    Extra {
+        a: u64,
+        b: String,
+    },
+    #[naumi(other)]
+    Unknown,
+}
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+#[naumi(varint_tag)]
+enum Borrowed<'a> {
+    #[naumi(tag = 300)]
+    A(u8),
+    #[naumi(other)]
+    Raw { tag: u32, payload: &'a [u8] },
+}
+
+fn v3() -> [MessageV3; 6] {
+    [
+        MessageV3::Ping(1),
+        MessageV3::Text { text: "hi".into() },
+        MessageV3::Empty,
+        MessageV3::Data(vec![1, 2, 3], 9),
+        MessageV3::Extra {
+            a: 5,
+            b: "x".into(),
+        },
+        MessageV3::Unknown,
+    ]
+}
+
+#[test]
+fn payloads_are_length_delimited() {
+    // Payload, its length, then the tag.
+    assert_eq!(MessageV1::Ping(9).to_bytes_return(), [9, 1, 0]);
+    assert_eq!(MessageV1::Unknown.to_bytes_return(), [0, 2]);
+    assert_eq!(MessageV2::Raw(9, vec![5]).to_bytes_return(), [5, 1, 9]);
+    assert_eq!(
+        MessageV2::Raw(9, vec![5])
+            .try_to_bytes_with(Layout::Forward)
+            .unwrap(),
+        [9, 1, 5]
+    );
+    assert_eq!(MessageV1::MAX_SIZE, None);
+}
+
+#[test]
+fn unknown_tags_go_to_the_unit_variant() {
+    for layout in LAYOUTS {
+        for message in v3() {
+            // A value after the enum, to check the skipped payload does not shift it.
+            let mut tx = Encoder::with_layout(Vec::new(), layout);
+            tx.prefixed(|tx| message.encode(tx), |tx| 7u8.encode(tx))
+                .unwrap();
+            let bytes = tx.into_inner();
+
+            let (decoded, after) =
+                decode_from_slice_with::<(MessageV1, u8)>(&bytes, layout).unwrap();
+            assert_eq!(after, 7);
+            match message {
+                MessageV3::Ping(p) => assert_eq!(decoded, MessageV1::Ping(p)),
+                MessageV3::Text { text } => assert_eq!(decoded, MessageV1::Text { text }),
+                _ => assert_eq!(decoded, MessageV1::Unknown),
+            }
+        }
+    }
+}
+
+#[test]
+fn captured_variants_are_written_back_unchanged() {
+    for layout in LAYOUTS {
+        for message in v3() {
+            let bytes = message.try_to_bytes_with(layout).unwrap();
+            let decoded = decode_from_slice_with::<MessageV2>(&bytes, layout).unwrap();
+            assert_eq!(decoded.encoded_len(), bytes.len());
+            assert_eq!(decoded.try_to_bytes_with(layout).unwrap(), bytes);
+            match message {
+                MessageV3::Data(..) => assert!(matches!(decoded, MessageV2::Raw(3, _))),
+                MessageV3::Extra { .. } => assert!(matches!(decoded, MessageV2::Raw(4, _))),
+                MessageV3::Unknown => assert!(matches!(decoded, MessageV2::Raw(5, _))),
+                _ => assert!(!matches!(decoded, MessageV2::Raw(..))),
+            }
+        }
+
+        let raw = Borrowed::Raw {
+            tag: 1000,
+            payload: &[1, 2, 3],
+        };
+        let bytes = raw.try_to_bytes_with(layout).unwrap();
+        assert_eq!(bytes.len(), raw.encoded_len());
+        assert_eq!(
+            decode_from_slice_with::<Borrowed>(&bytes, layout).unwrap(),
+            raw
+        );
+        let known = Borrowed::A(4);
+        let bytes = known.try_to_bytes_with(layout).unwrap();
+        assert_eq!(
+            decode_from_slice_with::<Borrowed>(&bytes, layout).unwrap(),
+            known
+        );
+    }
+}
+
+#[test]
+fn truncated_payload_is_an_error() {
+    for layout in LAYOUTS {
+        let bytes = MessageV3::Data(vec![1, 2, 3], 9)
+            .try_to_bytes_with(layout)
+            .unwrap();
+        for len in 0..bytes.len() {
+            let cut = match layout {
+                Layout::Reverse => &bytes[bytes.len() - len..],
+                Layout::Forward => &bytes[..len],
+            };
+            assert!(decode_from_slice_with::<MessageV1>(cut, layout).is_err());
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use naumi::nmacro::NaumiConvert;
+use naumi::types::encoder::Encoder;
+use naumi::types::error::ErrorKind;
+use naumi::types::iters::TinyVec;
+use naumi::types::layout::Layout;
+use naumi::types::string::TinyString;
+use naumi::types::{decode_from_slice_with, Convert};
+
+const LAYOUTS: [Layout; 2] = [Layout::Reverse, Layout::Forward];
+
+#[derive(NaumiConvert)]
+struct Profile {
+    id: u8,
+    bio: TinyString,
+}
+
+///
+/// 254 ASCII bytes followed by a two byte char, so the 255 byte cut falls inside it.
+///
+fn long_string() -> String {
+    let mut s = "a".repeat(254);
+    s.push('é');
+    s
+}
+
+fn truncated<'a, T: Convert<'a>>(value: &T, layout: Layout) -> Vec<u8> {
+    let mut tx = Encoder::with_layout(Vec::new(), layout);
+    tx.set_truncate(true);
+    value.encode(&mut tx).unwrap();
+    tx.into_inner()
+}
+
+#[test]
+fn overflow_is_an_error_by_default() {
+    for layout in LAYOUTS {
+        let profile = Profile {
+            id: 1,
+            bio: TinyString(long_string()),
+        };
+        let e = profile.try_to_bytes_with(layout).err().unwrap();
+        assert!(matches!(e.kind(), ErrorKind::LengthOverflow));
+        assert_eq!(e.to_string(), "length overflow");
+
+        let e = TinyVec(vec![0u16; 256])
+            .try_to_bytes_with(layout)
+            .err()
+            .unwrap();
+        assert!(matches!(e.kind(), ErrorKind::LengthOverflow));
+
+        // The largest values that fit are fine.
+        assert!(TinyString("a".repeat(255))
+            .try_to_bytes_with(layout)
+            .is_ok());
+        assert!(TinyVec(vec![0u16; 255]).try_to_bytes_with(layout).is_ok());
+    }
+}
+
+#[test]
+fn truncation_cuts_at_a_char_boundary() {
+    for layout in LAYOUTS {
+        let bytes = truncated(&TinyString(long_string()), layout);
+        assert_eq!(bytes.len(), 1 + 254);
+        let decoded = decode_from_slice_with::<TinyString>(&bytes, layout).unwrap();
+        assert_eq!(decoded.0, "a".repeat(254));
+
+        let mut s = "a".repeat(300);
+        s.insert(0, 'b');
+        let bytes = truncated(&TinyString(s), layout);
+        let decoded = decode_from_slice_with::<TinyString>(&bytes, layout).unwrap();
+        assert_eq!(decoded.0.len(), 255);
+        assert!(decoded.0.starts_with('b'));
+    }
+}
+
+#[test]
+fn truncation_keeps_the_first_elements() {
+    for layout in LAYOUTS {
+        let items: Vec<u16> = (0..300).collect();
+        let bytes = truncated(&TinyVec(items.clone()), layout);
+        let decoded = decode_from_slice_with::<TinyVec<u16>>(&bytes, layout).unwrap();
+        assert_eq!(decoded.0, items[..255]);
+
+        // Values after the truncated one are still in place.
+        let bytes = truncated(&(TinyVec(items), 7u8), layout);
+        let (decoded, after) =
+            decode_from_slice_with::<(TinyVec<u16>, u8)>(&bytes, layout).unwrap();
+        assert_eq!((decoded.0.len(), after), (255, 7));
+    }
+}
+
+#[test]
+#[should_panic(expected = "length overflow")]
+fn to_bytes_panics_on_overflow() {
+    TinyString("a".repeat(256)).to_bytes(&mut Vec::new());
+}
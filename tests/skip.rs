@@ -0,0 +1,141 @@
+use naumi::nmacro::NaumiConvert;
+use naumi::types::decoder::Decoder;
+use naumi::types::layout::Layout;
+use naumi::types::{decode_from_slice_with, Convert};
+
+const LAYOUTS: [Layout; 2] = [Layout::Reverse, Layout::Forward];
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+struct Session {
+    id: u32,
+    #[naumi(skip)]
+    cache: Vec<u8>,
+    name: String,
+    #[naumi(skip, default = "unknown_peer")]
+    peer: String,
+}
+
+///
+/// `Session` as it is on the wire.
+///
+#[derive(NaumiConvert)]
+struct SessionWire {
+    id: u32,
+    name: String,
+}
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+enum Event {
+    Connected {
+        id: u32,
+        #[naumi(skip)]
+        at: u64,
+    },
+    Data(#[naumi(skip, default = "unknown_peer")] String, Vec<u8>),
+}
+
+#[derive(NaumiConvert)]
+enum EventWire {
+    Connected { id: u32 },
+    Data(Vec<u8>),
+}
+
+#[derive(NaumiConvert)]
+struct Point {
+    x: u8,
+    #[naumi(skip)]
+    label: String,
+}
+
+fn unknown_peer() -> String {
+    "unknown".into()
+}
+
+fn session() -> Session {
+    Session {
+        id: 7,
+        cache: vec![1, 2, 3],
+        name: "ann".into(),
+        peer: "10.0.0.1".into(),
+    }
+}
+
+#[test]
+fn skipped_fields_are_not_written() {
+    for layout in LAYOUTS {
+        let wire = SessionWire {
+            id: 7,
+            name: "ann".into(),
+        };
+        let bytes = session().try_to_bytes_with(layout).unwrap();
+        assert_eq!(bytes, wire.try_to_bytes_with(layout).unwrap());
+        assert_eq!(session().encoded_len(), bytes.len());
+
+        let event = Event::Connected { id: 3, at: 99 };
+        let wire = EventWire::Connected { id: 3 };
+        assert_eq!(
+            event.try_to_bytes_with(layout).unwrap(),
+            wire.try_to_bytes_with(layout).unwrap()
+        );
+        let event = Event::Data("10.0.0.1".into(), vec![4, 5]);
+        let wire = EventWire::Data(vec![4, 5]);
+        assert_eq!(
+            event.try_to_bytes_with(layout).unwrap(),
+            wire.try_to_bytes_with(layout).unwrap()
+        );
+        assert_eq!(event.encoded_len(), wire.encoded_len());
+    }
+    assert_eq!(Point::MAX_SIZE, Some(1));
+}
+
+#[test]
+fn skipped_fields_are_filled_on_decode() {
+    for layout in LAYOUTS {
+        let bytes = session().try_to_bytes_with(layout).unwrap();
+        assert_eq!(
+            decode_from_slice_with::<Session>(&bytes, layout).unwrap(),
+            Session {
+                id: 7,
+                cache: vec![],
+                name: "ann".into(),
+                peer: "unknown".into(),
+            }
+        );
+
+        let bytes = Event::Connected { id: 3, at: 99 }
+            .try_to_bytes_with(layout)
+            .unwrap();
+        assert_eq!(
+            decode_from_slice_with::<Event>(&bytes, layout).unwrap(),
+            Event::Connected { id: 3, at: 0 }
+        );
+        let bytes = Event::Data("10.0.0.1".into(), vec![4, 5])
+            .try_to_bytes_with(layout)
+            .unwrap();
+        assert_eq!(
+            decode_from_slice_with::<Event>(&bytes, layout).unwrap(),
+            Event::Data("unknown".into(), vec![4, 5])
+        );
+    }
+}
+
+#[test]
+fn decode_into_resets_skipped_fields() {
+    for layout in LAYOUTS {
+        let bytes = session().try_to_bytes_with(layout).unwrap();
+        let mut reused = session();
+        reused
+            .decode_into(&mut Decoder::with_layout(&bytes, layout))
+            .unwrap();
+        assert_eq!((reused.cache.len(), reused.peer.as_str()), (0, "unknown"));
+
+        let bytes = Event::Connected { id: 3, at: 99 }
+            .try_to_bytes_with(layout)
+            .unwrap();
+        let mut reused = Event::Connected { id: 1, at: 99 };
+        reused
+            .decode_into(&mut Decoder::with_layout(&bytes, layout))
+            .unwrap();
+        assert_eq!(reused, Event::Connected { id: 3, at: 0 });
+    }
+}
@@ -0,0 +1,172 @@
+use naumi::nmacro::NaumiConvert;
+use naumi::types::encoder::Encoder;
+use naumi::types::error::ErrorKind;
+use naumi::types::layout::Layout;
+use naumi::types::{decode_from_slice, decode_from_slice_with, Convert};
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+enum Counted {
+    Zero,
+    Three = 3,
+    Four,
+    #[naumi(tag = 10)]
+    Ten = 7,
+    Eleven,
+}
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+enum Pinned {
+    #[naumi(tag = 5)]
+    A,
+    B(u8),
+    #[naumi(tag = 2)]
+    C {
+        x: u8,
+    },
+    D,
+}
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+#[naumi(varint_tag)]
+enum Wide {
+    A(u8),
+    #[naumi(tag = 200)]
+    B,
+    #[naumi(tag = 300)]
+    C(String),
+    #[naumi(tag = 100_000)]
+    D,
+}
+
+///
+/// Tagged 1 and 2, up to 0.3.5 the discriminants were ignored and the tags were 0 and 1.
+///
+#[derive(NaumiConvert, Debug, PartialEq)]
+enum Explicit {
+    A = 1,
+    B = 2,
+}
+
+///
+/// `Explicit` keeping the tags of 0.3.5.
+///
+#[derive(NaumiConvert, Debug, PartialEq)]
+enum ExplicitCompat {
+    #[naumi(tag = 0)]
+    A = 1,
+    #[naumi(tag = 1)]
+    B = 2,
+}
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+enum Never {}
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+#[naumi(varint_tag)]
+enum NeverWide {}
+
+#[test]
+fn tags_count_up_from_discriminants() {
+    assert_eq!(Counted::Zero.to_bytes_return(), [0]);
+    assert_eq!(Counted::Three.to_bytes_return(), [3]);
+    assert_eq!(Counted::Four.to_bytes_return(), [4]);
+    // `tag` wins over the discriminant, and the next variant counts up from it.
+    assert_eq!(Counted::Ten.to_bytes_return(), [10]);
+    assert_eq!(Counted::Eleven.to_bytes_return(), [11]);
+
+    assert_eq!(Pinned::A.to_bytes_return(), [5]);
+    assert_eq!(Pinned::B(1).to_bytes_return(), [1, 6]);
+    assert_eq!(Pinned::C { x: 1 }.to_bytes_return(), [1, 2]);
+    assert_eq!(Pinned::D.to_bytes_return(), [3]);
+
+    for value in [
+        Counted::Zero,
+        Counted::Three,
+        Counted::Four,
+        Counted::Ten,
+        Counted::Eleven,
+    ] {
+        let bytes = value.to_bytes_return();
+        assert_eq!(decode_from_slice::<Counted>(&bytes).unwrap(), value);
+    }
+    assert_eq!(
+        decode_from_slice::<Counted>(&[1]).unwrap_err().to_string(),
+        "Counted: unknown tag 1"
+    );
+}
+
+#[test]
+fn varint_tags() {
+    assert_eq!(Wide::A(7).to_bytes_return(), [7, 0]);
+    assert_eq!(Wide::B.to_bytes_return(), [1, 0xC8]);
+    assert_eq!(Wide::D.to_bytes_return(), [6, 0x8D, 0xA0]);
+    assert_eq!(Wide::MAX_SIZE, None);
+
+    for layout in [Layout::Reverse, Layout::Forward] {
+        for (value, tag_len) in [
+            (Wide::A(7), 1),
+            (Wide::B, 2),
+            (Wide::C("x".into()), 2),
+            (Wide::D, 3),
+        ] {
+            let bytes = value.try_to_bytes_with(layout).unwrap();
+            assert_eq!(bytes.len(), value.encoded_len());
+            let payload = match &value {
+                Wide::A(_) => 1,
+                Wide::C(s) => s.encoded_len(),
+                _ => 0,
+            };
+            assert_eq!(bytes.len(), tag_len + payload);
+            assert_eq!(
+                decode_from_slice_with::<Wide>(&bytes, layout).unwrap(),
+                value
+            );
+        }
+    }
+}
+
+#[test]
+fn explicit_discriminants_are_the_tags() {
+    assert_eq!(Explicit::A.to_bytes_return(), [1]);
+    assert_eq!(Explicit::B.to_bytes_return(), [2]);
+    assert_eq!(ExplicitCompat::A.to_bytes_return(), [0]);
+    assert_eq!(ExplicitCompat::B.to_bytes_return(), [1]);
+
+    // Bytes from 0.3.5 decode into the wrong variant, or fail, without the pinned tags.
+    assert_eq!(decode_from_slice::<Explicit>(&[1]).unwrap(), Explicit::A);
+    assert!(matches!(
+        decode_from_slice::<Explicit>(&[0]).unwrap_err().kind(),
+        ErrorKind::UnknownTag(0)
+    ));
+    assert_eq!(
+        decode_from_slice::<ExplicitCompat>(&[0]).unwrap(),
+        ExplicitCompat::A
+    );
+    assert_eq!(
+        decode_from_slice::<ExplicitCompat>(&[1]).unwrap(),
+        ExplicitCompat::B
+    );
+}
+
+#[test]
+fn empty_enums_reject_every_tag() {
+    assert_eq!(Never::MAX_SIZE, Some(0));
+    for layout in [Layout::Reverse, Layout::Forward] {
+        for tag in [0u8, 1, 255] {
+            let e = decode_from_slice_with::<Never>(&[tag], layout).unwrap_err();
+            assert!(matches!(e.kind(), ErrorKind::UnknownTag(t) if *t == tag as u32));
+            assert_eq!(e.type_name(), Some("Never"));
+        }
+        let mut tx = Encoder::with_layout(Vec::new(), layout);
+        tx.write_var_int(300).unwrap();
+        let e = decode_from_slice_with::<NeverWide>(tx.get_ref(), layout).unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::UnknownTag(300)));
+
+        // Only the absent value can be written.
+        let bytes = None::<Never>.try_to_bytes_with(layout).unwrap();
+        assert_eq!(
+            decode_from_slice_with::<Option<Never>>(&bytes, layout).unwrap(),
+            None
+        );
+    }
+}
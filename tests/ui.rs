@@ -0,0 +1,7 @@
+//! Misused derives have to fail with an error pointing at the attribute, the cases are in `tests/ui`.
+
+#[test]
+fn invalid_derives_do_not_compile() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,11 @@
+use naumi::nmacro::NaumiConvert;
+
+#[derive(NaumiConvert)]
+enum Duplicate {
+    A,
+    B,
+    #[naumi(tag = 0)]
+    C,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use naumi::nmacro::NaumiConvert;
+
+#[derive(NaumiConvert)]
+struct Config {
+    #[naumi(default = "default_port")]
+    port: u16,
+}
+
+fn default_port() -> u16 {
+    80
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use naumi::nmacro::NaumiConvert;
+
+const BASE: isize = 4;
+
+#[derive(NaumiConvert)]
+enum Computed {
+    A = BASE,
+    B,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use naumi::nmacro::NaumiConvert;
+
+#[derive(NaumiConvert)]
+enum Duplicate {
+    A = 1,
+    #[naumi(tag = 1)]
+    B,
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use naumi::nmacro::NaumiConvert;
+
+#[derive(NaumiConvert)]
+enum Message {
+    Ping,
+    #[naumi(other)]
+    Unknown(u8),
+}
+
+#[derive(NaumiConvert)]
+enum Request {
+    Get,
+    #[naumi(other)]
+    Unknown(u8, Vec<u8>, bool),
+}
+
+fn main() {}
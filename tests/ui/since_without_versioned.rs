@@ -0,0 +1,19 @@
+use naumi::nmacro::NaumiConvert;
+
+#[derive(NaumiConvert)]
+struct User {
+    id: u32,
+    #[naumi(since = 2)]
+    age: u8,
+}
+
+#[derive(NaumiConvert)]
+enum Event {
+    Joined {
+        id: u32,
+        #[naumi(since = 2)]
+        at: u64,
+    },
+}
+
+fn main() {}
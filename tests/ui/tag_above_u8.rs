@@ -0,0 +1,10 @@
+use naumi::nmacro::NaumiConvert;
+
+#[derive(NaumiConvert)]
+enum TooLarge {
+    A,
+    #[naumi(tag = 256)]
+    B,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use naumi::nmacro::NaumiConvert;
+
+#[derive(NaumiConvert)]
+union Bits {
+    int: u32,
+    float: f32,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use naumi::nmacro::NaumiConvert;
+
+#[derive(NaumiConvert)]
+struct Point {
+    #[naumi(rename = "y")]
+    x: u8,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use naumi::nmacro::NaumiConvert;
+
+#[derive(NaumiConvert)]
+#[naumi(packed)]
+struct Point {
+    x: u8,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use naumi::nmacro::NaumiConvert;
+
+#[derive(NaumiConvert)]
+enum Message {
+    #[naumi(id = 1)]
+    Ping,
+}
+
+fn main() {}
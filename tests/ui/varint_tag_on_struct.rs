@@ -0,0 +1,9 @@
+use naumi::nmacro::NaumiConvert;
+
+#[derive(NaumiConvert)]
+#[naumi(varint_tag)]
+struct Point {
+    x: u8,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use naumi::nmacro::NaumiConvert;
+
+#[derive(NaumiConvert)]
+struct Config {
+    #[naumi(skip, with = "port")]
+    port: u16,
+}
+
+fn main() {}
@@ -0,0 +1,88 @@
+use std::borrow::Cow;
+
+use naumi::types::decoder::Decoder;
+use naumi::types::error::ErrorKind;
+use naumi::types::layout::Layout;
+use naumi::types::string::{LongString, MediumString, ShortString, TinyString};
+use naumi::types::{decode_from_slice_with, Convert};
+
+const LAYOUTS: [Layout; 2] = [Layout::Reverse, Layout::Forward];
+
+///
+/// Encoding of a string holding `a\xFFb`, written as `axb` and patched afterwards.
+///
+fn invalid<'a, T: Convert<'a>>(value: T, layout: Layout) -> Vec<u8> {
+    let mut bytes = value.try_to_bytes_with(layout).unwrap();
+    let at = bytes.iter().position(|&b| b == b'x').unwrap();
+    bytes[at] = 0xFF;
+    bytes
+}
+
+///
+/// Run `$check!(type, constructor from a String)` for every owned string type.
+///
+macro_rules! for_all_strings {
+    ($check:ident) => {
+        $check!(String, String::from);
+        $check!(TinyString, TinyString);
+        $check!(ShortString, ShortString);
+        $check!(MediumString, MediumString);
+        $check!(LongString, LongString);
+        $check!(Cow<str>, Cow::<str>::Owned);
+    };
+}
+
+#[test]
+fn invalid_utf8_is_an_error() {
+    for layout in LAYOUTS {
+        macro_rules! check {
+            ($ty:ty, $new:expr) => {
+                let bytes = invalid($new("axb".to_string()), layout);
+                assert!(matches!(
+                    decode_from_slice_with::<$ty>(&bytes, layout)
+                        .err()
+                        .unwrap()
+                        .kind(),
+                    ErrorKind::InvalidUtf8
+                ));
+            };
+        }
+        for_all_strings!(check);
+        check!(&str, |s: String| s);
+    }
+}
+
+#[test]
+fn lossy_decoding_replaces_invalid_utf8() {
+    for layout in LAYOUTS {
+        macro_rules! check {
+            ($ty:ty, $new:expr) => {
+                let bytes = invalid($new("axb".to_string()), layout);
+                let expected = $new("a\u{FFFD}b".to_string())
+                    .try_to_bytes_with(layout)
+                    .unwrap();
+                let mut rx = Decoder::with_layout(&bytes, layout);
+                rx.set_lossy_utf8(true);
+                let decoded = <$ty>::decode(&mut rx).unwrap();
+                assert_eq!(decoded.try_to_bytes_with(layout).unwrap(), expected);
+
+                // Reused strings are replaced the same way.
+                let mut reused = $new("stale".to_string());
+                let mut rx = Decoder::with_layout(&bytes, layout);
+                rx.set_lossy_utf8(true);
+                reused.decode_into(&mut rx).unwrap();
+                assert_eq!(reused.try_to_bytes_with(layout).unwrap(), expected);
+            };
+        }
+        for_all_strings!(check);
+
+        // A borrowed `&str` cannot hold the replacement, so it still fails.
+        let bytes = invalid("axb", layout);
+        let mut rx = Decoder::with_layout(&bytes, layout);
+        rx.set_lossy_utf8(true);
+        assert!(matches!(
+            <&str>::decode(&mut rx).unwrap_err().kind(),
+            ErrorKind::InvalidUtf8
+        ));
+    }
+}
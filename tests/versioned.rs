@@ -0,0 +1,125 @@
+use naumi::nmacro::NaumiConvert;
+use naumi::types::decoder::Decoder;
+use naumi::types::layout::Layout;
+use naumi::types::{decode_from_slice_with, Convert};
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+#[naumi(versioned)]
+struct UserV1 {
+    id: u32,
+    name: String,
+}
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+#[naumi(versioned)]
+struct UserV2 {
+    id: u32,
+    name: String,
+    #[naumi(since = 2)]
+    age: u8,
+    #[naumi(since = 2, default = "default_level")]
+    level: u16,
+}
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+#[naumi(versioned)]
+struct UserV3 {
+    id: u32,
+    name: String,
+    #[naumi(since = 2)]
+    age: u8,
+    #[naumi(since = 2, default = "default_level")]
+    level: u16,
+    #[naumi(since = 3)]
+    tags: Vec<String>,
+}
+
+fn default_level() -> u16 {
+    1
+}
+
+const LAYOUTS: [Layout; 2] = [Layout::Reverse, Layout::Forward];
+
+fn v3() -> UserV3 {
+    UserV3 {
+        id: 7,
+        name: "ann".into(),
+        age: 30,
+        level: 12,
+        tags: vec!["admin".into(), "ops".into()],
+    }
+}
+
+#[test]
+fn new_reader_fills_missing_fields() {
+    let old = UserV1 {
+        id: 7,
+        name: "ann".into(),
+    };
+    for layout in LAYOUTS {
+        let bytes = old.try_to_bytes_with(layout).unwrap();
+        assert_eq!(
+            decode_from_slice_with::<UserV3>(&bytes, layout).unwrap(),
+            UserV3 {
+                id: 7,
+                name: "ann".into(),
+                age: 0,
+                level: 1,
+                tags: vec![],
+            }
+        );
+
+        let bytes = UserV2 {
+            id: 7,
+            name: "ann".into(),
+            age: 30,
+            level: 12,
+        }
+        .try_to_bytes_with(layout)
+        .unwrap();
+        let mut v3 = decode_from_slice_with::<UserV3>(&bytes, layout).unwrap();
+        assert_eq!((v3.age, v3.level, v3.tags.len()), (30, 12, 0));
+
+        // Reused values are reset to the default as well.
+        v3.tags.push("stale".into());
+        let bytes = old.try_to_bytes_with(layout).unwrap();
+        v3.decode_into(&mut Decoder::with_layout(&bytes, layout))
+            .unwrap();
+        assert_eq!((v3.age, v3.level, v3.tags.len()), (0, 1, 0));
+    }
+}
+
+#[test]
+fn old_reader_skips_new_fields() {
+    for layout in LAYOUTS {
+        let bytes = v3().try_to_bytes_with(layout).unwrap();
+        assert_eq!(
+            decode_from_slice_with::<UserV1>(&bytes, layout).unwrap(),
+            UserV1 {
+                id: 7,
+                name: "ann".into(),
+            }
+        );
+        let v2 = decode_from_slice_with::<UserV2>(&bytes, layout).unwrap();
+        assert_eq!((v2.age, v2.level), (30, 12));
+
+        // The values after the struct are still in place.
+        let bytes = (v3(), 5u8, v3()).try_to_bytes_with(layout).unwrap();
+        let (a, b, c) = decode_from_slice_with::<(UserV1, u8, UserV2)>(&bytes, layout).unwrap();
+        assert_eq!((a.id, b, c.level), (7, 5, 12));
+    }
+}
+
+#[test]
+fn round_trip() {
+    for layout in LAYOUTS {
+        let user = v3();
+        let bytes = user.try_to_bytes_with(layout).unwrap();
+        assert_eq!(bytes.len(), user.encoded_len());
+        assert_eq!(
+            decode_from_slice_with::<UserV3>(&bytes, layout).unwrap(),
+            user
+        );
+    }
+    assert_eq!(UserV3::MAX_SIZE, None);
+}
@@ -0,0 +1,152 @@
+use std::time::Duration;
+
+use naumi::nmacro::NaumiConvert;
+use naumi::types::decoder::Decoder;
+use naumi::types::layout::Layout;
+use naumi::types::{decode_from_slice_with, Convert};
+
+const LAYOUTS: [Layout; 2] = [Layout::Reverse, Layout::Forward];
+
+///
+/// `Duration` written as whole milliseconds.
+///
+mod millis {
+    use std::time::Duration;
+
+    use naumi::types::decoder::Decoder;
+    use naumi::types::encoder::{Encoder, Writer};
+    use naumi::types::error::Result;
+    use naumi::types::Convert;
+
+    pub fn encode<W: Writer>(value: &Duration, tx: &mut Encoder<W>) -> Result<()> {
+        (value.as_millis() as u64).encode(tx)
+    }
+
+    pub fn decode(rx: &mut Decoder) -> Result<Duration> {
+        Ok(Duration::from_millis(u64::decode(rx)?))
+    }
+
+    pub fn encoded_len(_value: &Duration) -> usize {
+        8
+    }
+}
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+struct Timer {
+    id: u8,
+    #[naumi(with = "millis")]
+    every: Duration,
+    name: String,
+}
+
+///
+/// `Timer` as it is on the wire.
+///
+#[derive(NaumiConvert)]
+struct TimerWire {
+    id: u8,
+    every: u64,
+    name: String,
+}
+
+#[derive(NaumiConvert, Debug, PartialEq)]
+enum Command {
+    Stop,
+    Sleep(u8, #[naumi(with = "millis")] Duration),
+    Retry {
+        #[naumi(with = "millis")]
+        after: Duration,
+    },
+}
+
+#[derive(NaumiConvert)]
+enum CommandWire {
+    Stop,
+    Sleep(u8, u64),
+    Retry { after: u64 },
+}
+
+#[derive(NaumiConvert)]
+struct Fixed {
+    id: u8,
+    every: u64,
+}
+
+#[derive(NaumiConvert)]
+struct FixedWith {
+    id: u8,
+    #[naumi(with = "millis")]
+    every: Duration,
+}
+
+fn timer() -> Timer {
+    Timer {
+        id: 1,
+        every: Duration::from_millis(1500),
+        name: "tick".into(),
+    }
+}
+
+#[test]
+fn struct_fields_round_trip() {
+    for layout in LAYOUTS {
+        let bytes = timer().try_to_bytes_with(layout).unwrap();
+        let wire = TimerWire {
+            id: 1,
+            every: 1500,
+            name: "tick".into(),
+        };
+        assert_eq!(bytes, wire.try_to_bytes_with(layout).unwrap());
+        assert_eq!(timer().encoded_len(), bytes.len());
+        assert_eq!(
+            decode_from_slice_with::<Timer>(&bytes, layout).unwrap(),
+            timer()
+        );
+
+        // `decode_into` goes through the module as well.
+        let mut reused = Timer {
+            id: 9,
+            every: Duration::ZERO,
+            name: "stale".into(),
+        };
+        reused
+            .decode_into(&mut Decoder::with_layout(&bytes, layout))
+            .unwrap();
+        assert_eq!(reused, timer());
+    }
+}
+
+#[test]
+fn variant_fields_round_trip() {
+    for layout in LAYOUTS {
+        let commands = [
+            (Command::Stop, CommandWire::Stop),
+            (
+                Command::Sleep(2, Duration::from_millis(250)),
+                CommandWire::Sleep(2, 250),
+            ),
+            (
+                Command::Retry {
+                    after: Duration::from_secs(3),
+                },
+                CommandWire::Retry { after: 3000 },
+            ),
+        ];
+        for (command, wire) in commands {
+            let bytes = command.try_to_bytes_with(layout).unwrap();
+            assert_eq!(bytes, wire.try_to_bytes_with(layout).unwrap());
+            assert_eq!(command.encoded_len(), bytes.len());
+            assert_eq!(
+                decode_from_slice_with::<Command>(&bytes, layout).unwrap(),
+                command
+            );
+        }
+    }
+}
+
+#[test]
+fn custom_codecs_have_no_max_size() {
+    assert_eq!(Fixed::MAX_SIZE, Some(9));
+    assert_eq!(FixedWith::MAX_SIZE, None);
+    assert_eq!(Command::MAX_SIZE, None);
+}
@@ -0,0 +1,95 @@
+use std::io::{self, BufWriter, Cursor, Write};
+
+use naumi::nmacro::NaumiConvert;
+use naumi::types::error::ErrorKind;
+use naumi::types::Convert;
+
+#[derive(NaumiConvert)]
+struct Message {
+    id: u32,
+    text: String,
+    tags: Vec<String>,
+}
+
+#[derive(NaumiConvert)]
+#[naumi(forward)]
+struct ForwardMessage {
+    id: u32,
+    text: String,
+}
+
+fn message() -> Message {
+    Message {
+        id: 7,
+        text: "hello".into(),
+        tags: vec!["a".into(), "b".into()],
+    }
+}
+
+///
+/// Accepts `limit` bytes, then fails like a closed connection.
+///
+struct Broken {
+    limit: usize,
+}
+
+impl Write for Broken {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.limit == 0 {
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"));
+        }
+        let len = buf.len().min(self.limit);
+        self.limit -= len;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn writers_get_the_same_bytes() {
+    let expected = message().to_bytes_return();
+
+    let mut tx = BufWriter::new(Vec::new());
+    message().to_writer(&mut tx).unwrap();
+    assert_eq!(tx.into_inner().unwrap(), expected);
+
+    let mut tx = Cursor::new(Vec::new());
+    message().to_writer(&mut tx).unwrap();
+    assert_eq!(tx.into_inner(), expected);
+
+    // Values follow each other in the stream.
+    let forward = ForwardMessage {
+        id: 1,
+        text: "hi".into(),
+    };
+    let mut tx = Cursor::new(vec![0u8; 32]);
+    forward.to_writer(&mut tx).unwrap();
+    forward.to_writer(&mut tx).unwrap();
+    let len = tx.position() as usize;
+    assert_eq!(len, 2 * forward.encoded_len());
+    assert_eq!(tx.get_ref()[..len / 2], forward.to_bytes_return());
+    assert_eq!(tx.get_ref()[len / 2..len], forward.to_bytes_return());
+}
+
+#[test]
+fn writer_errors_are_io_errors() {
+    let e = message().to_writer(Broken { limit: 3 }).unwrap_err();
+    match e.kind() {
+        ErrorKind::Io(e) => assert_eq!(e.kind(), io::ErrorKind::BrokenPipe),
+        kind => panic!("unexpected error {kind:?}"),
+    }
+    // The I/O error comes back unchanged.
+    let e = io::Error::from(e);
+    assert_eq!(
+        (e.kind(), e.to_string()),
+        (io::ErrorKind::BrokenPipe, "closed".into())
+    );
+
+    // A full fixed buffer fails the same way.
+    let mut buf = [0u8; 4];
+    let e = message().to_writer(Cursor::new(&mut buf[..])).unwrap_err();
+    assert!(matches!(e.kind(), ErrorKind::Io(e) if e.kind() == io::ErrorKind::WriteZero));
+}